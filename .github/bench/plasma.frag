@@ -0,0 +1,9 @@
+// Sample shader for the bench job, stepped through a fixed number of frames
+#define size 256 256
+
+void main()
+{
+    vec2 p = st * 8.0;
+    float v = sin(p.x + time) + sin(p.y + time * 0.5) + sin(length(p) - time);
+    color = vec4(0.5 + 0.5 * sin(v + vec3(0.0, 2.0, 4.0)), 1.0);
+}
@@ -1,5 +1,64 @@
 
 use std::{fmt::Display, result::Result};
+use super::logger;
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ErrorKind
+{
+    // The GL context is gone and nothing can be drawn anymore
+    Fatal,
+    // Shader, asset or transient GL errors shown on the error canvas
+    Recoverable,
+    // Platform requests that failed without affecting rendering
+    Ignorable
+}
+
+impl ErrorKind
+{
+    pub fn of(error: &anyhow::Error) -> Self
+    {
+        if let Some(error) = error.downcast_ref::<ogl::OGLError>()
+        {
+            return match error.is_context_lost()
+            {
+                true => Self::Fatal,
+                false => Self::Recoverable
+            }
+        }
+        match error.is::<winit::error::ExternalError>()
+        {
+            true => Self::Ignorable,
+            false => Self::Recoverable
+        }
+    }
+}
+
+// What becomes of an error, the error canvas cannot show its own errors
+// so they are logged while it stays up
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Recovery
+{
+    Abort,
+    Show,
+    LogError,
+    LogWarning
+}
+
+impl Recovery
+{
+    pub fn of(error: &anyhow::Error, errored: bool) -> Self
+    {
+        match (ErrorKind::of(error), errored)
+        {
+            (ErrorKind::Fatal, _) => Self::Abort,
+            (ErrorKind::Recoverable, false) => Self::Show,
+            (ErrorKind::Recoverable, true) => Self::LogError,
+            (ErrorKind::Ignorable, _) => Self::LogWarning
+        }
+    }
+}
 
 // ------------------------------------------------------------
 
@@ -25,16 +84,69 @@ where E: std::fmt::Display
         (
             |error|
             {
-                eprintln!("{error}");
-                msgbox::create
-                (
-                    "",
-                    &error.to_string(),
-                    msgbox::IconType::Error
-                ).unwrap();
+                logger::error(&error);
+                #[cfg(feature = "message-box")]
+                if message_box_enabled()
+                {
+                    let _ = msgbox::create
+                    (
+                        "",
+                        &error.to_string(),
+                        msgbox::IconType::Error
+                    );
+                }
                 std::process::exit(1)
             }
         ).unwrap()
     }
 }
 
+
+// ------------------------------------------------------------
+
+#[test]
+fn kinds() -> ()
+{
+    let error = |error: ogl::OGLError| ErrorKind::of(&error.into());
+    assert_eq!(error(ogl::OGLError::GL(ogl::CONTEXT_LOST)), ErrorKind::Fatal);
+    assert_eq!(error(ogl::OGLError::GL(ogl::OUT_OF_MEMORY)), ErrorKind::Recoverable);
+    assert_eq!
+    (
+        error(ogl::OGLError::ShaderCompilation(String::new())),
+        ErrorKind::Recoverable
+    );
+    assert_eq!(ErrorKind::of(&anyhow::anyhow!("missing file")), ErrorKind::Recoverable)
+}
+
+#[test]
+fn recoveries() -> ()
+{
+    let lost = anyhow::Error::from(ogl::OGLError::GL(ogl::CONTEXT_LOST));
+    let transient = anyhow::Error::from(ogl::OGLError::GL(ogl::OUT_OF_MEMORY));
+    assert_eq!(Recovery::of(&lost, false), Recovery::Abort);
+    assert_eq!(Recovery::of(&transient, false), Recovery::Show);
+    // Shown on the error canvas already, which keeps going
+    assert_eq!(Recovery::of(&lost, true), Recovery::Abort);
+    assert_eq!(Recovery::of(&transient, true), Recovery::LogError);
+    assert_eq!(Recovery::of(&anyhow::anyhow!("missing file"), true), Recovery::LogError)
+}
+
+// ------------------------------------------------------------
+
+// GLIS_MESSAGE_BOX=0 turns the box off, and it is never shown
+// without a display to show it on, e.g. in CI or over SSH
+#[cfg(feature = "message-box")]
+fn message_box_enabled() -> bool
+{
+    if std::env::var("GLIS_MESSAGE_BOX").is_ok_and(|value| value == "0")
+    {
+        return false
+    }
+    match cfg!(all(unix, not(target_os = "macos")))
+    {
+        true => ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|name| std::env::var_os(name).is_some()),
+        false => true
+    }
+}
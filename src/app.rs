@@ -1,13 +1,19 @@
 
-use std::{path::*, env};
+use std::path::*;
 use super::
 {
+    abort::*,
+    logger,
     watcher::*,
     window::*,
     timeline::*,
     canvas::*,
     glsl::*,
-    picture::*,
+    assets::*,
+    paths::*,
+    mesh::*,
+    overlay::*,
+    shaders::GLSLVersion,
     runtime::*
 };
 use winit::
@@ -18,71 +24,65 @@ use winit::
 
 // ------------------------------------------------------------
 
-impl CodeAnnotations
+impl AnnotatedGLSL
 {
-    fn runtime_setup
+    pub fn runtime_setup
     (
         &self,
         root: impl AsRef<Path>,
-        pointers: &ogl::FunctionPointers
+        textures: &mut TextureCache
     ) -> anyhow::Result<RuntimeSetup>
     {
+        let annotations = self.annotations();
+        let resolver = PathResolver::new(root);
         let this = RuntimeSetup
         {
-            resolution: self.resolution(),
-            feedback: self.feedback(),
-            timeline: Timeline::new(self.rate(), self.range()),
+            resolution: annotations.resolution(),
+            feedback: annotations.feedback(),
+            geometry: match (annotations.vertex(), annotations.mesh_path())
+            {
+                (Some(mode), _) => Geometry::Vertices(mode),
+                (None, Some(path)) => Geometry::Mesh
+                (
+                    Mesh::open
+                    (
+                        resolver.resolve(path)
+                            .map_err(|error| anyhow::anyhow!("Mesh {error}"))?
+                    )?,
+                    self.vertex_code().unwrap_or_default().to_string()
+                ),
+                (None, None) => Geometry::Quad
+            },
+            compute: annotations.compute().map
+            (
+                |mode| Compute
+                {
+                    code: self.compute_code().unwrap_or_default().to_string(),
+                    mode
+                }
+            ),
+            hud: annotations.hud(),
+            presentation: Presentation
+            {
+                compositing: annotations.compositing(),
+                color: annotations.color(),
+                antialiasing: annotations.antialiasing()
+            },
+            timeline: Timeline::new(annotations.rate(), annotations.range())
+                .with_stepping(annotations.stepping())
+                .with_period(annotations.time_period()),
             textures:
             {
-                let cwd = env::current_dir()?;
-                env::set_current_dir(root)?;
-                let mut textures = vec!();
-                for (name, path) in self.texture_paths()
+                let mut paths = vec!();
+                for (name, path) in annotations.texture_paths()
                 {
-                    let mut picture = Picture::open(path)?;
-                    picture.flipv();
-                    let picture: PictureData = picture.try_into()?;
-                    let texture = match &picture.pixel_data
-                    {
-                        PixelData::EightBit(data) => NamedTexture::new
-                        (
-                            pointers,
-                            ogl::Image::<u8>
-                            {
-                                data: Some(data),
-                                resolution: picture.resolution,
-                                channels: picture.channels.into()
-                            },
-                            &name
-                        ),
-                        PixelData::SixteenBit(data) => NamedTexture::new
-                        (
-                            pointers,
-                            ogl::Image::<u16>
-                            {
-                                data: Some(data),
-                                resolution: picture.resolution,
-                                channels: picture.channels.into()
-                            },
-                            &name
-                        ),
-                        PixelData::ThirtyTwoBit(data) => NamedTexture::new
-                        (
-                            pointers,
-                            ogl::Image::<f32>
-                            {
-                                data: Some(data),
-                                resolution: picture.resolution,
-                                channels: picture.channels.into()
-                            },
-                            &name
-                        )
-                    };
-                    textures.push(texture)
+                    let path = resolver.resolve(path)
+                        .map_err(|error| anyhow::anyhow!("Texture `{name}`: {error}"))?;
+                    paths.push((name.clone(), path))
                 }
-                env::set_current_dir(cwd)?;
-                textures
-            }
+                textures.load(paths, annotations.color().input)?
+            },
+            source_map: self.source_map().clone()
         };
         Ok(this)
     }
@@ -90,8 +90,24 @@ impl CodeAnnotations
 
 // ------------------------------------------------------------
 
-fn init_window(event_loop: &EventLoop<()>) -> anyhow::Result<GLWindow>
+// The context is negotiated for the given GLSL version, falling back
+// to the default one the built-in shaders need
+pub fn init_window
+(
+    event_loop: &EventLoop<()>,
+    glsl: Option<GLSLVersion>
+) -> anyhow::Result<GLWindow>
 {
+    let mut configurations = vec!(GLContextConfiguration::default());
+    if let Some(glsl) = glsl
+    {
+        let configuration = GLContextConfiguration
+        {
+            version: GLVersion::for_glsl(glsl),
+            ..Default::default()
+        };
+        configurations.insert(0, configuration)
+    }
     let window = Window::new
     (
         &event_loop, |builder| builder
@@ -104,11 +120,7 @@ fn init_window(event_loop: &EventLoop<()>) -> anyhow::Result<GLWindow>
             .with_resizable(false)
     ).map
     (
-        |window| GLWindow::new
-        (
-            window,
-            Default::default()
-        )
+        |window| GLWindow::new(window, configurations)
     )??;
     let pointers = window.pointers();
     unsafe
@@ -126,6 +138,8 @@ pub struct App
 {
     window: GLWindow,
     watcher: Option<CodeWatcher>,
+    textures: TextureCache,
+    overlay: Option<Overlay>,
     runtime: RuntimeState<f32> 
 }
 
@@ -137,12 +151,18 @@ impl App
         path: impl AsRef<Path>
     ) -> anyhow::Result<Self>
     {
-        let window = init_window(event_loop)?;
+        let glsl = std::fs::read_to_string(path.as_ref()).ok()
+            .and_then(|code| AnnotatedGLSL::new(&code).ok())
+            .map(|code| code.version());
+        let window = init_window(event_loop, glsl)?;
         let runtime = RuntimeState::new(window.pointers())?;
+        let textures = TextureCache::new(window.pointers());
         let mut this = Self
         {
             window,
             watcher: None,
+            textures,
+            overlay: None,
             runtime
         };
         if let Ok(watcher) = CodeWatcher::new(path)
@@ -156,9 +176,33 @@ impl App
         Ok(this)
     }
 
-    pub fn drag_window(&self)  -> Result<(), winit::error::ExternalError>
+    pub fn drag_window(&mut self) -> anyhow::Result<()>
     {
-        self.window.drag()
+        let result = self.window.drag().map_err(Into::into);
+        self.recover(result)
+    }
+
+    // Only fatal errors are returned, the others are shown
+    // on the error canvas or logged so that the show goes on
+    fn recover(&mut self, result: anyhow::Result<()>) -> anyhow::Result<()>
+    {
+        let error = match result
+        {
+            Ok(()) => return Ok(()),
+            Err(error) => error
+        };
+        match Recovery::of(&error, self.runtime.is_errored())
+        {
+            Recovery::Abort => Err(error),
+            Recovery::Show => match self.runtime.into_errored(&error)
+            {
+                Err(error) if error.is_context_lost() => Err(error.into()),
+                Err(error) => Ok(logger::error(error)),
+                Ok(()) => Ok(())
+            },
+            Recovery::LogError => Ok(logger::error(error)),
+            Recovery::LogWarning => Ok(logger::warning(error))
+        }
     }
 
     fn restart(&mut self) -> anyhow::Result<()>
@@ -168,14 +212,38 @@ impl App
         {
             Ok(code) => match AnnotatedGLSL::new(&code)
             {
+                Ok(code) if !self.window.version()
+                    .satisfies(&GLVersion::for_glsl(code.version())) =>
+                {
+                    let glsl = code.version();
+                    let required = GLVersion::for_glsl(glsl);
+                    let current = self.window.version();
+                    let message = match required.creatable(self.window.unavailable())
+                    {
+                        _ if required.compatibility && !GLVersion::COMPATIBILITY_PROFILE => format!
+                        (
+                            "GLSL {glsl} needs a compatibility profile, which this platform does not provide. \
+                            Legacy GLSL is unsupported, use #version 330 or newer"
+                        ),
+                        true => format!
+                            ("GLSL {glsl} needs an OpenGL {required} context but this one is {current}, restart glis to switch"),
+                        false => format!
+                            ("GLSL {glsl} needs an OpenGL {required} context but this system could not create one, this one is {current}")
+                    };
+                    self.runtime.into_errored(&message)?
+                }
                 Ok(code) => match watcher.filepath().parent()
                 {
-                    Some(root) => match code.annotations()
-                        .runtime_setup(root, self.runtime.pointers())
+                    Some(root) => match code
+                        .runtime_setup(root, &mut self.textures)
                     {
                         Ok(setup) =>
                         {
+                            let overlay = setup.presentation.compositing == Compositing::Overlay;
                             self.runtime.restart(&code.code(), setup)?;
+                            logger::info
+                                (format!("Loaded {}", watcher.filepath().display()));
+                            self.set_overlay(overlay);
                             self.window.set_size::<PhysicalSize<u32>>
                                 (self.runtime.resolution().into())
                         }
@@ -192,7 +260,27 @@ impl App
         Ok(())
     }
 
+    fn set_overlay(&mut self, enabled: bool) -> ()
+    {
+        self.overlay = match (enabled, self.overlay.take())
+        {
+            (true, None) => Some(Overlay::new(&self.window)),
+            (false, Some(overlay)) => 
+            {
+                overlay.close(&self.window);
+                None
+            }
+            (_, overlay) => overlay
+        }
+    }
+
     pub fn refresh(&mut self) -> anyhow::Result<()>
+    {
+        let result = self.try_refresh();
+        self.recover(result)
+    }
+
+    fn try_refresh(&mut self) -> anyhow::Result<()>
     {
         if let Some(watcher) = &self.watcher
         {
@@ -203,8 +291,20 @@ impl App
                 Err(error) => self.runtime.into_errored(&error)?
             }
         } 
-        if self.runtime.refresh()
+        self.textures.refresh();
+        for message in logger::take_notifications()
         {
+            self.runtime.notify(&message)
+        }
+        if self.runtime.refresh()?
+        {
+            if let Some(overlay) = self.overlay.as_mut().filter(|overlay| overlay.due())
+            {
+                if let Some(alpha) = self.runtime.alpha()?
+                {
+                    overlay.update(&alpha, self.runtime.resolution())
+                }
+            }
             self.window.context().swap_buffers()
         }
         Ok(())
@@ -0,0 +1,180 @@
+use std::{collections::*, fs, path::*, time::SystemTime};
+use super::{canvas::*, color::*, picture::*, watcher::*, logger};
+
+// ------------------------------------------------------------
+
+// Float images are already linear. Other encoded images are decoded by the GPU
+// when it has a matching sRGB format, or to floats before they are uploaded
+fn upload(texture: &NamedTexture, picture: PictureData, input: Transfer) -> ()
+{
+    let channels = picture.channels.into();
+    let resolution = picture.resolution;
+    let count = u8::from(channels) as usize;
+    let decoded = |values: &mut dyn Iterator<Item = f32>|
+        input.decode_pixels(values, count);
+    match (&picture.pixel_data, input)
+    {
+        (PixelData::EightBit(data), Transfer::Srgb) if count >= 3 => texture.upload_srgb
+        (
+            ogl::Image::<u8>{data: Some(data), resolution, channels}
+        ),
+        (PixelData::EightBit(data), Transfer::Srgb | Transfer::Gamma(_)) => texture.upload
+        (
+            ogl::Image::<f32>
+            {
+                data: Some(&decoded(&mut data.iter().map(|&value| value as f32 / 255.0))),
+                resolution,
+                channels
+            }
+        ),
+        (PixelData::SixteenBit(data), Transfer::Srgb | Transfer::Gamma(_)) => texture.upload
+        (
+            ogl::Image::<f32>
+            {
+                data: Some(&decoded(&mut data.iter().map(|&value| value as f32 / 65535.0))),
+                resolution,
+                channels
+            }
+        ),
+        (PixelData::EightBit(data), _) => texture.upload
+        (
+            ogl::Image::<u8>{data: Some(data), resolution, channels}
+        ),
+        (PixelData::SixteenBit(data), _) => texture.upload
+        (
+            ogl::Image::<u16>{data: Some(data), resolution, channels}
+        ),
+        (PixelData::ThirtyTwoBit(data), _) => texture.upload
+        (
+            ogl::Image::<f32>{data: Some(data), resolution, channels}
+        )
+    }
+}
+
+// ------------------------------------------------------------
+
+struct CachedTexture
+{
+    texture: NamedTexture,
+    modified: SystemTime,
+    input: Transfer,
+    watcher: Option<AssetWatcher>
+}
+
+impl CachedTexture
+{
+    fn open
+    (
+        pointers: &ogl::FunctionPointers,
+        path: &Path,
+        input: Transfer
+    ) -> anyhow::Result<Self>
+    {
+        let watcher = AssetWatcher::new(path)
+            .map_err(|error| logger::warning(format!("Not watching {}: {error}", path.display())))
+            .ok();
+        let mut this = Self
+        {
+            texture: NamedTexture::new(pointers, &path.to_string_lossy()),
+            modified: SystemTime::UNIX_EPOCH,
+            input,
+            watcher
+        };
+        this.reload(path)?;
+        Ok(this)
+    }
+
+    fn reload(&mut self, path: &Path) -> anyhow::Result<()>
+    {
+        let modified = fs::metadata(path)?.modified()?;
+        let mut picture = Picture::open(path)?;
+        picture.flipv();
+        upload(&self.texture, picture.try_into()?, self.input);
+        self.modified = modified;
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------
+
+// Decoded images stay on the GPU across shader reloads, keyed by their
+// canonical path and modification time, and are uploaded again in place
+// when they change on disk
+pub struct TextureCache
+{
+    pointers: ogl::FunctionPointers,
+    entries: HashMap<PathBuf, CachedTexture>
+}
+
+impl TextureCache
+{
+    pub fn new(pointers: &ogl::FunctionPointers) -> Self
+    {
+        Self{pointers: pointers.clone(), entries: HashMap::new()}
+    }
+
+    // Images no longer used are evicted once all the others have loaded,
+    // a different input transfer function uploads them again
+    pub fn load
+    (
+        &mut self,
+        textures: impl IntoIterator<Item = (String, PathBuf)>,
+        input: Transfer
+    ) -> anyhow::Result<Vec<NamedTexture>>
+    {
+        let mut used = HashSet::new();
+        let mut loaded = vec!();
+        for (name, path) in textures
+        {
+            let path = fs::canonicalize(&path)
+                .map_err(|error| anyhow::anyhow!("{}: {error}", path.display()))?;
+            let modified = fs::metadata(&path)?.modified()?;
+            match self.entries.get_mut(&path)
+            {
+                Some(entry) if entry.modified != modified || entry.input != input =>
+                {
+                    entry.input = input;
+                    entry.reload(&path)?
+                }
+                Some(_) => {}
+                None =>
+                {
+                    let entry = CachedTexture::open(&self.pointers, &path, input)?;
+                    self.entries.insert(path.clone(), entry);
+                }
+            }
+            loaded.push(self.entries[&path].texture.renamed(&name));
+            used.insert(path);
+        }
+        self.entries.retain(|path, _| used.contains(path));
+        Ok(loaded)
+    }
+
+    // An image that fails to load, e.g. while it is
+    // still being written, keeps its previous contents
+    pub fn refresh(&mut self) -> ()
+    {
+        for (path, entry) in &mut self.entries
+        {
+            let changed = match &mut entry.watcher
+            {
+                Some(watcher) => watcher.refresh(),
+                None => Ok(false)
+            };
+            let reloaded = changed.and_then
+            (
+                |changed| match changed
+                {
+                    true => entry.reload(path).map(|_| true),
+                    false => Ok(false)
+                }
+            );
+            match reloaded
+            {
+                Ok(true) => logger::info(format!("Reloaded {}", path.display())),
+                Ok(false) => {}
+                Err(error) => logger::warning(format!("{}: {error}", path.display()))
+            }
+        }
+    }
+}
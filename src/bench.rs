@@ -0,0 +1,273 @@
+use std::{path::*, fmt, fs, time::Instant};
+use ogl::*;
+use winit::event_loop::*;
+use super::{app::*, assets::*, canvas::*, glsl::*, logger};
+
+// ------------------------------------------------------------
+
+pub struct BenchOptions
+{
+    path: PathBuf,
+    frames: u32,
+    size: Option<[u32; 2]>,
+    output: Option<PathBuf>
+}
+
+impl BenchOptions
+{
+    pub const USAGE: &'static str =
+        "Usage: glis bench <shader> [--frames N] [--size WIDTH HEIGHT] [--output report.json]";
+
+    pub fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self>
+    {
+        let mut path = None;
+        let mut frames = 1000;
+        let mut size = None;
+        let mut output = None;
+        let value = |args: &mut dyn Iterator<Item = String>, flag: &str|
+            args.next().ok_or_else(|| anyhow::anyhow!("Expected a value for `{flag}`"));
+        while let Some(arg) = args.next()
+        {
+            match arg.as_str()
+            {
+                "--frames" => frames = value(&mut args, &arg)?.parse()?,
+                "--size" =>
+                {
+                    let width = value(&mut args, &arg)?.parse()?;
+                    let height = value(&mut args, &arg)?.parse()?;
+                    size = Some([width, height])
+                }
+                "--output" => output = Some(PathBuf::from(value(&mut args, &arg)?)),
+                flag if flag.starts_with("--") => anyhow::bail!
+                    ("Unknown option `{flag}`\n{}", Self::USAGE),
+                _ => path = Some(PathBuf::from(arg))
+            }
+        }
+        match (path, frames)
+        {
+            (None, _) => anyhow::bail!(Self::USAGE),
+            (_, 0) => anyhow::bail!("Expected at least one frame"),
+            (Some(path), _) => Ok(Self{path, frames, size, output})
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+// Timings in milliseconds, p99 by nearest rank
+#[derive(Debug, PartialEq)]
+struct Summary
+{
+    min: f64,
+    median: f64,
+    p99: f64,
+    mean: f64
+}
+
+impl Summary
+{
+    fn new(samples: &[f64]) -> Self
+    {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let rank = |percentile: f64|
+        {
+            let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        Self
+        {
+            min: sorted[0],
+            median: rank(50.0),
+            p99: rank(99.0),
+            mean: sorted.iter().sum::<f64>() / sorted.len() as f64
+        }
+    }
+
+    fn json(&self) -> String
+    {
+        format!
+        (
+            "{{\"min\": {:.4}, \"median\": {:.4}, \"p99\": {:.4}, \"mean\": {:.4}}}",
+            self.min, self.median, self.p99, self.mean
+        )
+    }
+}
+
+impl fmt::Display for Summary
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!
+        (
+            formatter,
+            "{:>9.3} {:>9.3} {:>9.3} {:>9.3}",
+            self.min, self.median, self.p99, self.mean
+        )
+    }
+}
+
+// ------------------------------------------------------------
+
+fn quote(string: &str) -> String
+{
+    let mut quoted = String::from("\"");
+    for character in string.chars()
+    {
+        match character
+        {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            character if character.is_control() =>
+                quoted += &format!("\\u{:04x}", character as u32),
+            character => quoted.push(character)
+        }
+    }
+    quoted + "\""
+}
+
+// ------------------------------------------------------------
+
+struct Report
+{
+    shader: PathBuf,
+    renderer: String,
+    frames: u32,
+    resolution: [u32; 2],
+    gpu: Summary,
+    cpu: Summary
+}
+
+impl Report
+{
+    fn json(&self) -> String
+    {
+        format!
+        (
+            "{{\n  \"shader\": {},\n  \"renderer\": {},\n  \"frames\": {},\n  \
+            \"resolution\": [{}, {}],\n  \"gpu_ms\": {},\n  \"cpu_ms\": {}\n}}\n",
+            quote(&self.shader.to_string_lossy()),
+            quote(&self.renderer),
+            self.frames,
+            self.resolution[0],
+            self.resolution[1],
+            self.gpu.json(),
+            self.cpu.json()
+        )
+    }
+}
+
+impl fmt::Display for Report
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        writeln!
+        (
+            formatter,
+            "{} ({} frames at {}x{}, {})",
+            self.shader.display(),
+            self.frames,
+            self.resolution[0],
+            self.resolution[1],
+            self.renderer
+        )?;
+        writeln!(formatter, "{:<7}{:>9} {:>9} {:>9} {:>9}", "ms", "min", "median", "p99", "mean")?;
+        writeln!(formatter, "{:<7}{}", "gpu", self.gpu)?;
+        write!(formatter, "{:<7}{}", "cpu", self.cpu)
+    }
+}
+
+// ------------------------------------------------------------
+
+// Frames are stepped rather than timed so that rendering runs as fast as
+// possible, CPU time is the time spent issuing the frame's commands. Works
+// with software rasterizers, e.g. LIBGL_ALWAYS_SOFTWARE=1 under Xvfb
+pub fn run(event_loop: &EventLoop<()>, options: BenchOptions) -> anyhow::Result<()>
+{
+    let code = AnnotatedGLSL::new(&fs::read_to_string(&options.path)?)?;
+    let window = init_window(event_loop, Some(code.version()))?;
+    let pointers = window.pointers();
+    let root = options.path.parent().unwrap_or(Path::new("."));
+    let setup = code.runtime_setup(root, &mut TextureCache::new(pointers))?;
+    let resolution = options.size.unwrap_or(setup.resolution);
+    let mut player = CanvasPlayer::<f32>::new
+    (
+        pointers,
+        setup.timeline.stepped(),
+        code.code(),
+        setup.textures,
+        resolution,
+        setup.feedback,
+        &setup.geometry,
+        setup.compute.as_ref(),
+        false,
+        setup.presentation
+    ).map_err(|error| anyhow::anyhow!(setup.source_map.translate(&error.to_string())))?;
+    let queries: Vec<Query> = (0..options.frames)
+        .map(|_| Query::new(pointers))
+        .collect();
+    let mut cpu = vec!();
+    for query in &queries
+    {
+        let start = Instant::now();
+        query.begin(TIME_ELAPSED);
+        player.refresh()?;
+        query.end(TIME_ELAPSED);
+        cpu.push(start.elapsed().as_secs_f64() * 1000.0)
+    }
+    let gpu: Vec<f64> = queries.iter()
+        .map(|query| query.result() as f64 / 1e6)
+        .collect();
+    let report = Report
+    {
+        shader: options.path,
+        renderer: pointers.get_string(RENDERER),
+        frames: options.frames,
+        resolution,
+        gpu: Summary::new(&gpu),
+        cpu: Summary::new(&cpu)
+    };
+    logger::info(&report);
+    match &options.output
+    {
+        Some(path) => fs::write(path, report.json())?,
+        None => print!("{}", report.json())
+    }
+    Ok(())
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn options() -> ()
+{
+    let args = |line: &'static str| line.split_whitespace().map(String::from);
+    let options = BenchOptions::parse
+        (args("shader.frag --frames 10 --size 1920 1080 --output out.json"))
+        .unwrap();
+    assert_eq!(options.path, PathBuf::from("shader.frag"));
+    assert_eq!(options.frames, 10);
+    assert_eq!(options.size, Some([1920, 1080]));
+    assert_eq!(options.output, Some(PathBuf::from("out.json")));
+    let options = BenchOptions::parse(args("shader.frag")).unwrap();
+    assert_eq!((options.frames, options.size), (1000, None));
+    for invalid in ["", "shader.frag --size 10", "shader.frag --frames 0", "a --quiet"]
+    {
+        BenchOptions::parse(args(invalid)).map(|_| ()).unwrap_err();
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn summary() -> ()
+{
+    let samples: Vec<f64> = (1..=200).rev().map(f64::from).collect();
+    assert_eq!
+    (
+        Summary::new(&samples),
+        Summary{min: 1.0, median: 100.0, p99: 198.0, mean: 100.5}
+    );
+    assert_eq!(Summary::new(&[3.0]).p99, 3.0);
+    assert_eq!(quote("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"")
+}
@@ -2,10 +2,13 @@
 use std::
 {
     marker::PhantomData,
-    ops::Index
+    ops::{Deref, Index},
+    collections::HashMap,
+    rc::Rc,
+    time::{Duration, Instant}
 };
 use ogl::*;
-use super::timeline::*;
+use super::{timeline::*, mesh::*, hud::*, color::*, shaders::{GLSLVersion, TEXT_SHADER}};
 
 // ------------------------------------------------------------
 
@@ -17,6 +20,7 @@ impl QuadVertices
     {
         let vbo = VertexBufferObject::new(pointers);
         vbo.bind();
+        vbo.label("quad vertices");
         vbo.fill
         (
             &[
@@ -42,108 +46,694 @@ impl QuadVertices
 
 // ------------------------------------------------------------
 
-struct QuadProgram
+struct ShaderProgram
 {
     program: Program,
+    uniforms: HashMap<String, ActiveVariable>
+}
+
+impl ShaderProgram
+{
+    fn link(pointers: &FunctionPointers, shaders: &[&Shader]) -> OGLResult<Self>
+    {
+        let program = Program::link(pointers, shaders)?;
+        let uniforms = program.active_uniforms().into_iter()
+            .map(|uniform| (uniform.name.clone(), uniform))
+            .collect();
+        Ok(Self{program, uniforms})
+    }
+
+    fn pointers(&self) -> &FunctionPointers
+    {
+        self.program.pointers()
+    }
+
+    fn use_program(&self) -> ()
+    {
+        self.program.r#use()
+    }
+
+    fn label(&self, label: &str) -> ()
+    {
+        self.program.label(&format!("{label} program"))
+    }
+
+    fn attribute(&self, name: &str) -> OGLResult<GLint>
+    {
+        self.program.location(name, LocationOf::Attribute)
+    }
+
+    fn is_active(&self, name: &str) -> bool
+    {
+        self.uniforms.contains_key(name)
+    }
+
+    fn set_uniform<D>(&self, name: &str, data: D) -> OGLResult<()>
+    where D: ToUniform
+    {
+        let uniform = self.uniforms.get(name).ok_or_else
+        (
+            || OGLError::UniformNotFound(name.to_string())
+        )?;
+        if !D::accepts(uniform.kind)
+        {
+            return Err
+            (
+                OGLError::UniformTypeMismatch
+                    (name.to_string(), uniform.kind, D::UNIFORM_TYPE)
+            )
+        }
+        data.to_uniform
+        (
+            self.pointers(),
+            uniform.location
+        );
+        Ok(())
+    }
+
+    fn handle<D>(&self, name: &str) -> OGLResult<Option<UniformHandle<D>>>
+    where D: ToUniform
+    {
+        self.uniforms.get(name)
+            .map(|uniform| self.program.handle(uniform))
+            .transpose()
+    }
+
+    fn set<D>(&self, handle: &UniformHandle<D>, data: D) -> OGLResult<()>
+    where D: ToUniform
+    {
+        handle.set(&self.program, data)
+    }
+
+    // Inactive uniforms are not an error, the
+    // shader may have optimized them out
+    fn set_uniform_if_active<D>(&self, name: &str, data: D) -> OGLResult<()>
+    where D: ToUniform
+    {
+        match self.set_uniform(name, data)
+        {
+            Err(OGLError::UniformNotFound(_)) => Ok(()),
+            result => result
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+// Compiles a shader labelled after the program it belongs to
+fn compile_shader
+(
+    pointers: &FunctionPointers,
+    kind: GLenum,
+    code: &str,
+    label: &str
+) -> OGLResult<Shader>
+{
+    let shader = Shader::compile(pointers, kind, &format!("{code}\0"))?;
+    let stage = match kind
+    {
+        VERTEX_SHADER => "vertex",
+        COMPUTE_SHADER => "compute",
+        _ => "fragment"
+    };
+    shader.label(&format!("{label} {stage} shader"));
+    Ok(shader)
+}
+
+// ------------------------------------------------------------
+
+struct QuadProgram
+{
+    program: ShaderProgram,
     vao: VertexArrayObject
 }
 
+impl Deref for QuadProgram
+{
+    type Target = ShaderProgram;
+    fn deref(&self) -> &Self::Target
+    {
+        &self.program
+    }
+}
+
 impl QuadProgram
 {
     fn new
     (
         vertices: &QuadVertices,
-        fragment_code: &str
+        fragment_code: &str,
+        label: &str
+    ) -> OGLResult<Self>
+    {
+        let version = GLSLVersion::of(fragment_code).unwrap_or_default();
+        Self::with_vertex_shader(vertices, &version.quad_vertex_shader(), fragment_code, label)
+    }
+
+    fn with_vertex_shader
+    (
+        vertices: &QuadVertices,
+        vertex_code: &str,
+        fragment_code: &str,
+        label: &str
     ) -> OGLResult<Self>
     {
         let pointers = vertices.pointers();
-        let program = Program::link
+        let program = ShaderProgram::link
         (
            pointers,
            &[
-                &Shader::compile
+                &compile_shader
                 (
-                    &pointers,
+                    pointers,
                     VERTEX_SHADER,
-                    "
-                    #version 100
-                    attribute vec2 corners;
-                    varying vec2 st;
-                    void main()
-                    {
-                        gl_Position = vec4(corners, 0.0, 1.0);
-                        st = corners * 0.5 + 0.5;
-                    }
-                    \0"
+                    vertex_code,
+                    label
                 )?,
-                &Shader::compile
+                &compile_shader
                 (
-                    &pointers,
+                    pointers,
                     FRAGMENT_SHADER,
-                    &format!("{fragment_code}\0"),
+                    fragment_code,
+                    label
                 )?
            ]
         )?;
-        let location = program.location
-        (
-            &"corners",
-            LocationOf::Attribute
-        )?;
+        let location = program.attribute("corners")?;
         let vao = VertexArrayObject::new(pointers);
         vao.bind();
+        program.label(label);
+        vao.label(&format!("{label} vertex array"));
         vertices.bind();
         vao.attach_buffer::<f64>(location as _, 2)?;
         Ok(Self{program, vao})
     }
 
-    fn pointers(&self) -> &FunctionPointers
+    fn bind_vao(&self) -> ()
     {
-        self.program.pointers()
+        self.vao.bind()
     }
 
-    fn use_program(&self) -> ()
+    fn draw(&self) -> ()
     {
-        self.program.r#use()
+        self.pointers().draw_elements
+        (
+            TRIANGLES,
+            &[0u8, 1, 2, 0, 3, 2]
+        )
     }
+}
 
-    fn bind_vao(&self) -> ()
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Primitive
+{
+    Points,
+    Lines,
+    LineStrip,
+    LineLoop,
+    Triangles,
+    TriangleStrip,
+    TriangleFan
+}
+
+impl From<Primitive> for GLenum
+{
+    fn from(primitive: Primitive) -> Self
     {
-        self.vao.bind()
+        match primitive
+        {
+            Primitive::Points => POINTS,
+            Primitive::Lines => LINES,
+            Primitive::LineStrip => LINE_STRIP,
+            Primitive::LineLoop => LINE_LOOP,
+            Primitive::Triangles => TRIANGLES,
+            Primitive::TriangleStrip => TRIANGLE_STRIP,
+            Primitive::TriangleFan => TRIANGLE_FAN
+        }
     }
-    
-    fn set_uniform<D>(&self, name: &str, data: D) -> OGLResult<()>
-    where D: ToUniform
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Blending
+{
+    Alpha,
+    Additive,
+    Premultiplied
+}
+
+impl Blending
+{
+    fn enable(&self, pointers: &FunctionPointers) -> ()
+    {
+        pointers.enable(BLEND);
+        match self
+        {
+            Self::Alpha => pointers.blend_func(SRC_ALPHA, ONE_MINUS_SRC_ALPHA),
+            Self::Additive => pointers.blend_func(ONE, ONE),
+            Self::Premultiplied => pointers.blend_func(ONE, ONE_MINUS_SRC_ALPHA)
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+// How the canvas alpha reaches the window. Opaque ignores it, the checkerboard
+// previews it and the overlay lets the desktop show through, premultiplied
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum Compositing
+{
+    #[default]
+    Opaque,
+    Checkerboard,
+    Overlay
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Downsampling
+{
+    Box,
+    Lanczos
+}
+
+// The canvas renders at `supersampling` times the window resolution and is
+// filtered down when blitted. Color buffers with more than one sample are
+// multisampled and resolved after each frame
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Antialiasing
+{
+    pub supersampling: u32,
+    pub downsampling: Downsampling,
+    pub samples: u32
+}
+
+impl Default for Antialiasing
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            supersampling: 1,
+            downsampling: Downsampling::Box,
+            samples: 1
+        }
+    }
+}
+
+impl Antialiasing
+{
+    fn render_resolution(&self, resolution: [u32; 2]) -> [u32; 2]
+    {
+        resolution.map(|size| size * self.supersampling)
+    }
+}
+
+// How the canvas is rendered and shown in the window
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub struct Presentation
+{
+    pub compositing: Compositing,
+    pub color: ColorSettings,
+    pub antialiasing: Antialiasing
+}
+
+// ------------------------------------------------------------
+
+impl From<Transfer> for GLint
+{
+    fn from(transfer: Transfer) -> Self
+    {
+        match transfer
+        {
+            Transfer::Linear => 0,
+            Transfer::Srgb => 1,
+            Transfer::Gamma(_) => 2
+        }
+    }
+}
+
+// Zero is no tonemapping
+impl From<Tonemap> for GLint
+{
+    fn from(tonemap: Tonemap) -> Self
+    {
+        match tonemap
+        {
+            Tonemap::Reinhard => 1,
+            Tonemap::Aces => 2
+        }
+    }
+}
+
+impl From<Compositing> for GLint
+{
+    fn from(compositing: Compositing) -> Self
+    {
+        match compositing
+        {
+            Compositing::Opaque => 0,
+            Compositing::Checkerboard => 1,
+            Compositing::Overlay => 2
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct VertexMode
+{
+    pub count: u32,
+    pub primitive: Primitive,
+    pub depth: bool,
+    pub blending: Option<Blending>
+}
+
+// ------------------------------------------------------------
+
+struct VertexProgram
+{
+    program: ShaderProgram,
+    vao: VertexArrayObject,
+    mode: VertexMode
+}
+
+impl Deref for VertexProgram
+{
+    type Target = ShaderProgram;
+    fn deref(&self) -> &Self::Target
     {
-        let location = self.program.location
+        &self.program
+    }
+}
+
+impl VertexProgram
+{
+    fn new
+    (
+        pointers: &FunctionPointers,
+        vertex_code: &str,
+        mode: VertexMode
+    ) -> OGLResult<Self>
+    {
+        let version = GLSLVersion::of(vertex_code).unwrap_or_default();
+        let program = ShaderProgram::link
         (
-            name,
-            LocationOf::Uniform
+           pointers,
+           &[
+                &compile_shader
+                (
+                    pointers,
+                    VERTEX_SHADER,
+                    vertex_code,
+                    "main"
+                )?,
+                &compile_shader
+                (
+                    pointers,
+                    FRAGMENT_SHADER,
+                    &version.color_fragment_shader(),
+                    "main"
+                )?
+           ]
         )?;
-        data.to_uniform
+        program.use_program();
+        program.set_uniform_if_active("vertex_count", mode.count as GLfloat)?;
+        // Core profiles refuse to draw without a bound vertex array,
+        // an empty one is enough since geometry comes from gl_VertexID
+        let vao = VertexArrayObject::new(pointers);
+        vao.bind();
+        program.label("main");
+        vao.label("main vertex array");
+        Ok(Self{program, vao, mode})
+    }
+
+    fn depth(&self) -> bool
+    {
+        self.mode.depth
+    }
+
+    fn bind_vao(&self) -> ()
+    {
+        self.vao.bind()
+    }
+
+    fn draw(&self) -> ()
+    {
+        let pointers = self.pointers();
+        if self.mode.depth
+        {
+            pointers.enable(DEPTH_TEST)
+        }
+        if let Some(blending) = self.mode.blending
+        {
+            blending.enable(pointers)
+        }
+        pointers.enable(PROGRAM_POINT_SIZE);
+        pointers.draw_arrays
         (
-            self.pointers(),
-            location
+            self.mode.primitive.into(),
+            0,
+            self.mode.count as _
         );
-        Ok(())
+        pointers.disable(PROGRAM_POINT_SIZE);
+        pointers.disable(BLEND);
+        pointers.disable(DEPTH_TEST)
     }
+}
 
-    fn draw(&self) -> ()
+// ------------------------------------------------------------
+
+struct MeshProgram
+{
+    program: ShaderProgram,
+    vao: VertexArrayObject,
+    #[allow(unused)]
+    buffers: Vec<VertexBufferObject>,
+    count: u32
+}
+
+impl Deref for MeshProgram
+{
+    type Target = ShaderProgram;
+    fn deref(&self) -> &Self::Target
     {
-        self.pointers().draw_elements
+        &self.program
+    }
+}
+
+impl MeshProgram
+{
+    fn new
+    (
+        pointers: &FunctionPointers,
+        vertex_code: &str,
+        fragment_code: &str,
+        mesh: &Mesh
+    ) -> OGLResult<Self>
+    {
+        let program = ShaderProgram::link
         (
-            TRIANGLES,
-            &[0u8, 1, 2, 0, 3, 2]
-        )
+           pointers,
+           &[
+                &compile_shader
+                (
+                    pointers,
+                    VERTEX_SHADER,
+                    vertex_code,
+                    "main"
+                )?,
+                &compile_shader
+                (
+                    pointers,
+                    FRAGMENT_SHADER,
+                    fragment_code,
+                    "main"
+                )?
+           ]
+        )?;
+        let vao = VertexArrayObject::new(pointers);
+        vao.bind();
+        program.label("main");
+        vao.label("main vertex array");
+        let mut buffers = vec!();
+        for (name, data, components) in
+        [
+            ("position", &mesh.positions, 3),
+            ("normal", &mesh.normals, 3),
+            ("uv", &mesh.uvs, 2)
+        ]
+        {
+            // Attributes the shader does not read get optimized out
+            if let Ok(location) = program.attribute(name)
+            {
+                let vbo = VertexBufferObject::new(pointers);
+                vbo.bind();
+                vbo.label(&format!("mesh {name}"));
+                vbo.fill(data);
+                vao.attach_buffer::<GLfloat>(location as _, components)?;
+                buffers.push(vbo)
+            }
+        }
+        let [minimum, maximum] = mesh.bounds();
+        program.use_program();
+        program.set_uniform_if_active("bounds_min", minimum)?;
+        program.set_uniform_if_active("bounds_max", maximum)?;
+        let this = Self
+        {
+            program,
+            vao,
+            buffers,
+            count: mesh.vertex_count()
+        };
+        Ok(this)
+    }
+
+    fn bind_vao(&self) -> ()
+    {
+        self.vao.bind()
+    }
+
+    fn draw(&self) -> ()
+    {
+        let pointers = self.pointers();
+        pointers.enable(DEPTH_TEST);
+        pointers.draw_arrays(TRIANGLES, 0, self.count as _);
+        pointers.disable(DEPTH_TEST)
+    }
+}
+
+// ------------------------------------------------------------
+
+pub enum Geometry
+{
+    Quad,
+    Vertices(VertexMode),
+    Mesh(Mesh, String)
+}
+
+// ------------------------------------------------------------
+
+enum MainProgram
+{
+    Quad(QuadProgram),
+    Vertex(VertexProgram),
+    Mesh(MeshProgram)
+}
+
+impl From<QuadProgram> for MainProgram
+{
+    fn from(quad: QuadProgram) -> Self
+    {
+        Self::Quad(quad)
+    }
+}
+
+impl From<VertexProgram> for MainProgram
+{
+    fn from(vertex: VertexProgram) -> Self
+    {
+        Self::Vertex(vertex)
+    }
+}
+
+impl From<MeshProgram> for MainProgram
+{
+    fn from(mesh: MeshProgram) -> Self
+    {
+        Self::Mesh(mesh)
+    }
+}
+
+impl Deref for MainProgram
+{
+    type Target = ShaderProgram;
+    fn deref(&self) -> &Self::Target
+    {
+        match self
+        {
+            Self::Quad(quad) => quad,
+            Self::Vertex(vertex) => vertex,
+            Self::Mesh(mesh) => mesh
+        }
+    }
+}
+
+impl MainProgram
+{
+    fn new
+    (
+        vertices: &QuadVertices,
+        code: &str,
+        geometry: &Geometry
+    ) -> OGLResult<Self>
+    {
+        match geometry
+        {
+            Geometry::Quad => QuadProgram::with_vertex_shader
+            (
+                vertices,
+                &GLSLVersion::of(code).unwrap_or_default().tiled_quad_vertex_shader(),
+                code,
+                "main"
+            ).map(Into::into),
+            Geometry::Vertices(mode) => VertexProgram
+                ::new(vertices.pointers(), code, *mode)
+                .map(Into::into),
+            Geometry::Mesh(mesh, vertex_code) => MeshProgram
+                ::new(vertices.pointers(), vertex_code, code, mesh)
+                .map(Into::into)
+        }
+    }
+
+    fn depth(&self) -> bool
+    {
+        match self
+        {
+            Self::Quad(_) => false,
+            Self::Vertex(vertex) => vertex.depth(),
+            Self::Mesh(_) => true
+        }
+    }
+
+    fn bind_vao(&self) -> ()
+    {
+        match self
+        {
+            Self::Quad(quad) => quad.bind_vao(),
+            Self::Vertex(vertex) => vertex.bind_vao(),
+            Self::Mesh(mesh) => mesh.bind_vao()
+        }
+    }
+
+    fn draw(&self) -> ()
+    {
+        match self
+        {
+            Self::Quad(quad) => quad.draw(),
+            Self::Vertex(vertex) => vertex.draw(),
+            Self::Mesh(mesh) => mesh.draw()
+        }
     }
 }
 
 // ------------------------------------------------------------
 
-struct BlitterProgram(QuadProgram);
+struct BlitterProgram(QuadProgram, Compositing);
 
 impl BlitterProgram
 {
-    fn new(vbo: &QuadVertices, unit: GLint) -> OGLResult<Self>
+    // Tonemapping only applies to float canvases
+    fn new<D>
+    (
+        vbo: &QuadVertices,
+        unit: GLint,
+        presentation: Presentation
+    ) -> OGLResult<Self>
+    where D: TextureComponentDataType
     {    
         let quad = QuadProgram::new
         (
@@ -153,19 +743,149 @@ impl BlitterProgram
             in vec2 st;
             out vec4 color;
             uniform sampler2D image;
+            uniform int compositing;
+            uniform int tonemap;
+            uniform int transfer;
+            uniform float gamma;
+            uniform bool dither;
+            uniform int supersampling;
+            uniform bool lanczos;
+            float lanczos2(float x)
+            {
+                x = abs(x);
+                if (x < 1e-5)
+                {
+                    return 1.0;
+                }
+                if (x >= 2.0)
+                {
+                    return 0.0;
+                }
+                float pix = 3.14159265 * x;
+                return 2.0 * sin(pix) * sin(pix / 2.0) / (pix * pix);
+            }
+            vec4 fetch(ivec2 texel, ivec2 size)
+            {
+                return texelFetch(image, clamp(texel, ivec2(0), size - 1), 0);
+            }
+            vec4 downsampled()
+            {
+                ivec2 size = textureSize(image, 0);
+                vec2 center = st * vec2(size);
+                vec4 sum = vec4(0.0);
+                if (!lanczos)
+                {
+                    ivec2 first = ivec2(floor(center)) - supersampling / 2;
+                    for (int y = 0; y < supersampling; y++)
+                    {
+                        for (int x = 0; x < supersampling; x++)
+                        {
+                            sum += fetch(first + ivec2(x, y), size);
+                        }
+                    }
+                    return sum / float(supersampling * supersampling);
+                }
+                // Two lobes on each side, measured in window pixels
+                int radius = 2 * supersampling;
+                ivec2 first = ivec2(floor(center)) - radius;
+                float total = 0.0;
+                for (int y = 0; y <= 2 * radius; y++)
+                {
+                    for (int x = 0; x <= 2 * radius; x++)
+                    {
+                        ivec2 texel = first + ivec2(x, y);
+                        vec2 offset = (vec2(texel) + 0.5 - center) / float(supersampling);
+                        float weight = lanczos2(offset.x) * lanczos2(offset.y);
+                        sum += weight * fetch(texel, size);
+                        total += weight;
+                    }
+                }
+                return sum / total;
+            }
+            vec3 tonemapped(vec3 value)
+            {
+                switch (tonemap)
+                {
+                    case 1:
+                        return value / (1.0 + value);
+                    case 2:
+                        return clamp
+                        (
+                            (value * (2.51 * value + 0.03))
+                                / (value * (2.43 * value + 0.59) + 0.14),
+                            0.0, 1.0
+                        );
+                    default:
+                        return value;
+                }
+            }
+            vec3 encoded(vec3 value)
+            {
+                value = max(value, 0.0);
+                switch (transfer)
+                {
+                    case 1:
+                        return mix
+                        (
+                            value * 12.92,
+                            1.055 * pow(value, vec3(1.0 / 2.4)) - 0.055,
+                            step(0.0031308, value)
+                        );
+                    case 2:
+                        return pow(value, vec3(1.0 / gamma));
+                    default:
+                        return value;
+                }
+            }
+            float noise(vec2 position)
+            {
+                return fract(sin(dot(position, vec2(12.9898, 78.233))) * 43758.5453);
+            }
             void main()
             {
-                color = texture
-                (
-                    image,
-                    vec2(st.x, st.y)
-                );
+                vec4 texel = supersampling > 1
+                    ? downsampled()
+                    : texture(image, vec2(st.x, st.y));
+                vec3 rgb = encoded(tonemapped(texel.rgb));
+                if (dither)
+                {
+                    // Triangular noise of one 8-bit step hides banding
+                    vec2 position = gl_FragCoord.xy;
+                    rgb += (noise(position) + noise(position + 0.5) - 1.0) / 255.0;
+                }
+                vec2 cell = floor(gl_FragCoord.xy / 8.0);
+                float checker = mod(cell.x + cell.y, 2.0) * 0.2 + 0.6;
+                switch (compositing)
+                {
+                    case 1:
+                        color = vec4(mix(vec3(checker), rgb, texel.a), 1.0);
+                        break;
+                    case 2:
+                        color = vec4(rgb * texel.a, texel.a);
+                        break;
+                    default:
+                        color = vec4(rgb, 1.0);
+                }
             }
-            "
+            ",
+            "blitter"
         )?;
+        let Presentation{compositing, color, antialiasing} = presentation;
+        let tonemap = color.tonemap.filter(|_| D::TYPE_ENUM == FLOAT);
         quad.use_program();
         quad.set_uniform("image", unit)?;
-        Ok(Self(quad))
+        quad.set_uniform_if_active("compositing", GLint::from(compositing))?;
+        quad.set_uniform_if_active("tonemap", tonemap.map_or(0, GLint::from))?;
+        quad.set_uniform_if_active("transfer", GLint::from(color.output))?;
+        if let Transfer::Gamma(gamma) = color.output
+        {
+            quad.set_uniform_if_active("gamma", gamma)?
+        }
+        quad.set_uniform_if_active("dither", color.dither as GLint)?;
+        quad.set_uniform_if_active("supersampling", antialiasing.supersampling as GLint)?;
+        quad.set_uniform_if_active
+            ("lanczos", (antialiasing.downsampling == Downsampling::Lanczos) as GLint)?;
+        Ok(Self(quad, compositing))
     }
 
     fn use_program(&self) -> ()
@@ -180,7 +900,112 @@ impl BlitterProgram
 
     fn blit(&self) -> ()
     {
-        self.0.draw()
+        match self.1
+        {
+            Compositing::Overlay =>
+            {
+                let pointers = self.0.pointers();
+                Blending::Premultiplied.enable(pointers);
+                self.0.draw();
+                pointers.disable(BLEND)
+            }
+            _ => self.0.draw()
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+struct TextProgram(QuadProgram);
+
+impl TextProgram
+{
+    fn new(vertices: &QuadVertices) -> OGLResult<Self>
+    {
+        Ok(Self(QuadProgram::new(vertices, TEXT_SHADER, "text")?))
+    }
+
+    // Drawn over the blitted canvas
+    fn draw
+    (
+        &self,
+        lines: &[String],
+        grid: [usize; 2],
+        bottom: bool,
+        resolution: [u32; 2]
+    ) -> OGLResult<()>
+    {
+        let program = &self.0;
+        let pointers = program.pointers();
+        program.use_program();
+        program.set_uniform("glyphs", glyphs(lines, grid))?;
+        program.set_uniform_if_active("grid", [grid[0] as GLint, grid[1] as _])?;
+        program.set_uniform_if_active("bottom", bottom as GLint)?;
+        program.set_uniform_if_active
+            ("resolution", [resolution[0] as GLfloat, resolution[1] as _])?;
+        program.bind_vao();
+        Blending::Alpha.enable(pointers);
+        program.draw();
+        pointers.disable(BLEND);
+        Ok(())
+    }
+}
+
+// Passes run untimed without a HUD
+fn measure<T>(hud: &mut Option<FrameStats>, pass: Pass, run: impl FnOnce() -> T) -> T
+{
+    match hud
+    {
+        Some(hud) => hud.timer(pass).measure(run),
+        None => run()
+    }
+}
+
+// ------------------------------------------------------------
+
+// Rendered into instead of the texture and resolved into it
+struct Multisampled
+{
+    framebuffer: FramebufferObject,
+    #[allow(unused)]
+    color: Renderbuffer,
+    depth: Option<Renderbuffer>
+}
+
+impl Multisampled
+{
+    fn new
+    (
+        pointers: &FunctionPointers,
+        resolution: [u32; 2],
+        depth: bool,
+        samples: u32,
+        internal_format: GLenum,
+        label: &str
+    ) -> OGLResult<Self>
+    {
+        let framebuffer = FramebufferObject::new(pointers);
+        framebuffer.bind();
+        framebuffer.label(&format!("{label} multisampled framebuffer"));
+        let color = Renderbuffer::new(pointers);
+        color.bind();
+        color.label(&format!("{label} multisampled color"));
+        color.storage_multisample(samples, internal_format, resolution)?;
+        framebuffer.attach_color_renderbuffer(&color)?;
+        let depth = match depth
+        {
+            true =>
+            {
+                let renderbuffer = Renderbuffer::new(pointers);
+                renderbuffer.bind();
+                renderbuffer.label(&format!("{label} multisampled depth"));
+                renderbuffer.storage_multisample(samples, DEPTH_COMPONENT24, resolution)?;
+                framebuffer.attach_depth(&renderbuffer)?;
+                Some(renderbuffer)
+            }
+            false => None
+        };
+        Ok(Self{framebuffer, color, depth})
     }
 }
 
@@ -190,6 +1015,9 @@ struct ColorBuffer<D>
 {
     framebuffer: FramebufferObject,
     texture: Texture,
+    depth: Option<Renderbuffer>,
+    multisampled: Option<Multisampled>,
+    resolution: [u32; 2],
     data: PhantomData<D>
 }
 
@@ -201,14 +1029,19 @@ impl<D> ColorBuffer<D>
     fn new
     (
         pointers: &FunctionPointers,
-        resolution: [u32; 2]
+        resolution: [u32; 2],
+        depth: bool,
+        samples: u32,
+        label: &str
     ) -> OGLResult<Self>
     where D: TextureComponentDataType
     {
         let framebuffer = FramebufferObject::new(pointers);
         framebuffer.bind();
+        framebuffer.label(&format!("{label} framebuffer"));
         let texture = Texture::new(pointers);
         texture.bind();
+        texture.label(label);
         texture.setup
         (
             None,
@@ -224,10 +1057,35 @@ impl<D> ColorBuffer<D>
         };
         texture.fill(image, Self::MIMAP_FILTER.is_some());
         framebuffer.attach_color(&texture)?;
+        let multisampled = match samples > 1
+        {
+            true => Some
+            (
+                Multisampled::new
+                    (pointers, resolution, depth, samples, color_format::<D>(), label)?
+            ),
+            false => None
+        };
+        let depth = match depth && multisampled.is_none()
+        {
+            true =>
+            {
+                let renderbuffer = Renderbuffer::new(pointers);
+                renderbuffer.bind();
+                renderbuffer.label(&format!("{label} depth"));
+                renderbuffer.storage(DEPTH_COMPONENT24, resolution);
+                framebuffer.attach_depth(&renderbuffer)?;
+                Some(renderbuffer)
+            }
+            false => None
+        };
         let this = Self
         {
             framebuffer,
             texture,
+            depth,
+            multisampled,
+            resolution,
             data: PhantomData 
         };
         Ok(this)
@@ -240,13 +1098,47 @@ impl<D> ColorBuffer<D>
 
     fn bind_framebuffer(&self) -> ()
     {
-        self.framebuffer.bind()
+        match &self.multisampled
+        {
+            Some(multisampled) => multisampled.framebuffer.bind(),
+            None => self.framebuffer.bind()
+        }
+    }
+
+    // Makes what was rendered available to the texture
+    fn resolve(&self) -> ()
+    {
+        if let Some(multisampled) = &self.multisampled
+        {
+            multisampled.framebuffer.blit_color(&self.framebuffer, self.resolution)
+        }
+    }
+
+    fn has_depth(&self) -> bool
+    {
+        match &self.multisampled
+        {
+            Some(multisampled) => multisampled.depth.is_some(),
+            None => self.depth.is_some()
+        }
     }
 
     fn bind_texture(&self) -> ()
     {
         self.texture.bind()
     }
+
+    fn clear(&self) -> ()
+    {
+        self.pointers().clear
+        (
+            match self.has_depth()
+            {
+                true => COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT,
+                false => COLOR_BUFFER_BIT
+            }
+        )
+    }
 }
 
 // ------------------------------------------------------------
@@ -271,20 +1163,26 @@ impl<D, const N: usize> ColorBuffers<D, N>
     fn new
     (
         pointers: &FunctionPointers,
-        resolution: [u32; 2]
+        resolution: [u32; 2],
+        depth: bool,
+        samples: u32,
+        label: &str
     ) -> OGLResult<Self>
     where D: TextureComponentDataType
     {
         assert_ne!(N, 0);
         let mut list = vec!();
-        for _ in 0..N
+        for index in 0..N
         {
             list.push
             (
                 ColorBuffer::<D>::new
                 (
                     pointers,
-                    resolution
+                    resolution,
+                    depth,
+                    samples,
+                    &format!("{label} {index}")
                 )?
             )
         }
@@ -297,11 +1195,6 @@ impl<D, const N: usize> ColorBuffers<D, N>
         Ok(this)
     }
 
-    fn pointers(&self) -> &FunctionPointers
-    {
-        self[0].pointers()
-    }
-
     fn cursor(&self) -> usize
     {
         self.cursor
@@ -317,7 +1210,8 @@ impl<D, const N: usize> ColorBuffers<D, N>
         for buffer in &self.list
         {
             buffer.bind_framebuffer();
-            self.pointers().clear(COLOR_BUFFER_BIT)
+            buffer.clear();
+            buffer.resolve()
         }
         self.cursor = 0
     }
@@ -327,7 +1221,7 @@ impl<D, const N: usize> ColorBuffers<D, N>
 
 pub struct NamedTexture
 {
-    texture: Texture,
+    texture: Rc<Texture>,
     name: String
 }
 
@@ -336,27 +1230,52 @@ impl NamedTexture
     const MIMAP_FILTER: Option<InterpolationType>
         = None;
 
-    pub fn new<D>
-    (
-        pointers: &FunctionPointers,
-        image: Image<D>,
-        name: &str
-    ) -> Self
-    where D: TextureComponentDataType
+    // Has no storage until an image is uploaded
+    pub fn new(pointers: &FunctionPointers, name: &str) -> Self
     {
         let texture = Texture::new(pointers);
         texture.bind();
-        texture.setup
+        texture.label(&format!("texture {name}"));
+        Self
+        {
+            texture: Rc::new(texture),
+            name: name.to_string()
+        }
+    }
+
+    fn prepare(&self) -> ()
+    {
+        self.texture.bind();
+        self.texture.setup
         (
             None,
             InterpolationType::Nearest,
             InterpolationType::Nearest,
             Self::MIMAP_FILTER
-        );
-        texture.fill(image, Self::MIMAP_FILTER.is_some());
+        )
+    }
+
+    // Every texture sharing this one's storage sees the new image
+    pub fn upload<D>(&self, image: Image<D>) -> ()
+    where D: TextureComponentDataType
+    {
+        self.prepare();
+        self.texture.fill(image, Self::MIMAP_FILTER.is_some())
+    }
+
+    // Decoded to linear by the GPU when sampled
+    pub fn upload_srgb(&self, image: Image<u8>) -> ()
+    {
+        self.prepare();
+        self.texture.fill_as(image, SRGB8_ALPHA8, Self::MIMAP_FILTER.is_some())
+    }
+
+    // Shares the storage under another uniform name
+    pub fn renamed(&self, name: &str) -> Self
+    {
         Self
         {
-            texture,
+            texture: self.texture.clone(),
             name: name.to_string()
         }
     }
@@ -453,10 +1372,11 @@ impl Viewport
 
 struct SimpleCanvas<D>
 {
-    programs: (QuadProgram, BlitterProgram),
+    programs: (MainProgram, BlitterProgram),
     samplers: Vec<Sampler>,
     colorbuffer: ColorBuffer<D>,
-    resolution: [u32; 2]
+    resolution: [u32; 2],
+    antialiasing: Antialiasing
 }
 
 impl<D> SimpleCanvas<D>
@@ -464,32 +1384,37 @@ impl<D> SimpleCanvas<D>
     fn new
     (
         vertices: &QuadVertices,
-        code: &str,
+        main: MainProgram,
         textures: Vec<NamedTexture>,
-        resolution: [u32; 2]
+        resolution: [u32; 2],
+        presentation: Presentation
     ) -> OGLResult<Self>
     where D: TextureComponentDataType
     {
-        let main = QuadProgram::new(&vertices, code)?;
         main.use_program();
         let mut samplers = vec!();
         for (index, textures) in textures.into_iter().enumerate()
         {
-            main.set_uniform(textures.name(), index as GLint)?;
+            main.set_uniform_if_active(textures.name(), index as GLint)?;
             samplers.push(Sampler::new(textures, index as _))
         }
-        let blitter = BlitterProgram::new(&vertices, 0)?;
+        let blitter = BlitterProgram::new::<D>(&vertices, 0, presentation)?;
+        let antialiasing = presentation.antialiasing;
         let colorbuffer = ColorBuffer::new
         (
             vertices.pointers(),
-            resolution
+            antialiasing.render_resolution(resolution),
+            main.depth(),
+            antialiasing.samples,
+            "color buffer"
         )?;
         let this = Self
         {
             programs: (main, blitter),
             samplers,
             colorbuffer,
-            resolution
+            resolution,
+            antialiasing
         };
         Ok(this)
     }
@@ -504,12 +1429,14 @@ impl<D> SimpleCanvas<D>
         self.resolution
     }
 
-    fn set_uniform<U>(&self, name: &str, data: U) -> OGLResult<()>
-    where U: ToUniform
+    fn render_resolution(&self) -> [u32; 2]
     {
-        let (main, _) = &self.programs;
-        main.use_program();
-        main.set_uniform(name, data)
+        self.antialiasing.render_resolution(self.resolution)
+    }
+
+    fn main(&self) -> &MainProgram
+    {
+        &self.programs.0
     }
 
     fn render(&mut self) -> ()
@@ -521,16 +1448,17 @@ impl<D> SimpleCanvas<D>
             sampler.bind_texture()
         }
         self.colorbuffer.bind_framebuffer();
-        self.pointers().clear(COLOR_BUFFER_BIT);
+        self.colorbuffer.clear();
         let (main, _) = &self.programs;
         main.use_program();
         main.bind_vao();
         Viewport::new
         (
             [0, 0],
-            self.resolution
+            self.render_resolution()
         ).set(pointers);
-        main.draw()
+        main.draw();
+        self.colorbuffer.resolve()
     }
 
     fn blit
@@ -541,7 +1469,6 @@ impl<D> SimpleCanvas<D>
     ) -> ()
     {
         let pointers = self.pointers();
-        pointers.bind_default_framebuffer();
         pointers.clear(clear);
         Viewport::new
         (
@@ -561,10 +1488,11 @@ impl<D> SimpleCanvas<D>
 
 struct FeedbackCanvas<D>
 {
-    programs: (QuadProgram, BlitterProgram),
+    programs: (MainProgram, BlitterProgram),
     samplers: Vec<Sampler>,
     colorbuffers: ColorBuffers<D, 2>,
-    resolution: [u32; 2]
+    resolution: [u32; 2],
+    antialiasing: Antialiasing
 }
 
 impl<D> FeedbackCanvas<D>
@@ -572,34 +1500,39 @@ impl<D> FeedbackCanvas<D>
     fn new
     (
         vertices: &QuadVertices,
-        code: &str,
+        main: MainProgram,
         textures: Vec<NamedTexture>,
-        resolution: [u32; 2]
+        resolution: [u32; 2],
+        presentation: Presentation
     ) -> OGLResult<Self>
     where D: TextureComponentDataType
     {
-        let main = QuadProgram::new(&vertices, code)?;
         main.use_program();
-        main.set_uniform("previous", 0 as GLint)?;
+        main.set_uniform_if_active("previous", 0 as GLint)?;
         let mut samplers = vec!();
         for (index, texture) in textures.into_iter().enumerate()
         {
             let unit = index + 1;
-            main.set_uniform(texture.name(), unit as GLint)?;
+            main.set_uniform_if_active(texture.name(), unit as GLint)?;
             samplers.push(Sampler::new(texture, unit as _))
         }
-        let blitter = BlitterProgram::new(&vertices, 0)?;
+        let blitter = BlitterProgram::new::<D>(&vertices, 0, presentation)?;
+        let antialiasing = presentation.antialiasing;
         let colorbuffers = ColorBuffers::new
         (
             vertices.pointers(),
-            resolution
+            antialiasing.render_resolution(resolution),
+            main.depth(),
+            antialiasing.samples,
+            "feedback buffer"
         )?;
         let this = Self
         {
             programs: (main, blitter),
             samplers,
             colorbuffers,
-            resolution
+            resolution,
+            antialiasing
         };
         Ok(this)
     }
@@ -619,12 +1552,14 @@ impl<D> FeedbackCanvas<D>
         self.resolution
     }
 
-    fn set_uniform<U>(&self, name: &str, data: U) -> OGLResult<()>
-    where U: ToUniform
+    fn render_resolution(&self) -> [u32; 2]
     {
-        let (main, _) = &self.programs;
-        main.use_program();
-        main.set_uniform(name, data)
+        self.antialiasing.render_resolution(self.resolution)
+    }
+
+    fn main(&self) -> &MainProgram
+    {
+        &self.programs.0
     }
 
     fn render(&mut self) -> ()
@@ -639,7 +1574,7 @@ impl<D> FeedbackCanvas<D>
         let pointers = self.pointers();
         let cursor = self.colorbuffers.cursor();
         self.colorbuffers[cursor].bind_framebuffer();
-        self.pointers().clear(COLOR_BUFFER_BIT);
+        self.colorbuffers[cursor].clear();
         pointers.active_texture(0);
         self.colorbuffers[1 - cursor].bind_texture();
         let (main, _) = &self.programs;
@@ -648,9 +1583,10 @@ impl<D> FeedbackCanvas<D>
         Viewport::new
         (
             [0, 0],
-            self.resolution
+            self.render_resolution()
         ).set(pointers);
-        main.draw()
+        main.draw();
+        self.colorbuffers[cursor].resolve()
     }
 
     fn blit
@@ -661,7 +1597,6 @@ impl<D> FeedbackCanvas<D>
     ) -> ()
     {
         let pointers = self.pointers();
-        pointers.bind_default_framebuffer();
         pointers.clear(clear);
         Viewport::new
         (
@@ -707,20 +1642,21 @@ impl<D> CanvasKind<D>
     fn new
     (
         vertices: &QuadVertices,
-        code: &str,
+        main: MainProgram,
         textures: Vec<NamedTexture>,
         resolution: [u32; 2],
-        feedback: bool
+        feedback: bool,
+        presentation: Presentation
     ) -> OGLResult<Self>
     where D: TextureComponentDataType
     {
         match feedback
         {
             true => FeedbackCanvas
-                ::new(vertices, code, textures, resolution)
+                ::new(vertices, main, textures, resolution, presentation)
                 .map(Into::into),
             false => SimpleCanvas
-                ::new(vertices, code, textures, resolution)
+                ::new(vertices, main, textures, resolution, presentation)
                 .map(Into::into)
         }
     }
@@ -736,15 +1672,25 @@ impl<D> CanvasKind<D>
         }
     }
 
-    fn set_uniform<U>(&self, name: &str, data: U) -> OGLResult<()>
-    where U: ToUniform
+    fn render_resolution(&self) -> [u32; 2]
+    {
+        match self
+        {
+            Self::Simple(simple) =>
+                simple.render_resolution(),
+            Self::Feedback(feedback) =>
+                feedback.render_resolution()
+        }
+    }
+
+    fn main(&self) -> &MainProgram
     {
         match self
         {
-            Self::Simple(simple) => simple
-                .set_uniform(name, data),
-            Self::Feedback(feedback) => feedback
-                .set_uniform(name, data)
+            Self::Simple(simple) =>
+                simple.main(),
+            Self::Feedback(feedback) =>
+                feedback.main()
         }
     }
 
@@ -793,18 +1739,22 @@ impl<D> Canvas<D>
         code: &str,
         textures: Vec<NamedTexture>,
         resolution: [u32; 2],
-        feedback: bool
+        feedback: bool,
+        geometry: &Geometry,
+        presentation: Presentation
     ) -> OGLResult<Self>
     where D: TextureComponentDataType
     {
         let vertices = QuadVertices::new(pointers)?;
+        let main = MainProgram::new(&vertices, code, geometry)?;
         let kind = CanvasKind::new
         (
             &vertices,
-            code,
+            main,
             textures,
             resolution,
-            feedback
+            feedback,
+            presentation
         )?;
         Ok(Self{vertices, kind})
     }
@@ -819,10 +1769,14 @@ impl<D> Canvas<D>
         self.kind.resolution()
     }
 
-    fn set_uniform<U>(&self, name: &str, data: U) -> OGLResult<()>
-    where U: ToUniform
+    fn render_resolution(&self) -> [u32; 2]
+    {
+        self.kind.render_resolution()
+    }
+
+    fn main(&self) -> &MainProgram
     {
-        self.kind.set_uniform(name, data)
+        self.kind.main()
     }
 
     fn render(&mut self) -> ()
@@ -838,10 +1792,268 @@ impl<D> Canvas<D>
 
 // ------------------------------------------------------------
 
+struct BuiltinValues
+{
+    time: GLfloat,
+    time_hi: GLfloat,
+    time_lo: GLfloat,
+    frame: GLint,
+    resolution: [GLfloat; 2],
+    aspect: GLfloat,
+    delta_time: GLfloat,
+    date: [GLfloat; 4],
+    tile: [GLfloat; 4],
+    tile_origin: [GLfloat; 2]
+}
+
+// ------------------------------------------------------------
+
+struct BuiltinUniforms
+{
+    time: Option<UniformHandle<GLfloat>>,
+    time_hi: Option<UniformHandle<GLfloat>>,
+    time_lo: Option<UniformHandle<GLfloat>>,
+    frame: Option<UniformHandle<GLint>>,
+    resolution: Option<UniformHandle<[GLfloat; 2]>>,
+    aspect: Option<UniformHandle<GLfloat>>,
+    delta_time: Option<UniformHandle<GLfloat>>,
+    date: Option<UniformHandle<[GLfloat; 4]>>,
+    tile: Option<UniformHandle<[GLfloat; 4]>>,
+    tile_origin: Option<UniformHandle<[GLfloat; 2]>>
+}
+
+impl BuiltinUniforms
+{
+    fn new(program: &ShaderProgram) -> OGLResult<Self>
+    {
+        let this = Self
+        {
+            time: program.handle("time")?,
+            time_hi: program.handle("time_hi")?,
+            time_lo: program.handle("time_lo")?,
+            frame: program.handle("frame")?,
+            resolution: program.handle("resolution")?,
+            aspect: program.handle("aspect")?,
+            delta_time: program.handle("delta_time")?,
+            date: program.handle("date")?,
+            tile: program.handle("glis_tile")?,
+            tile_origin: program.handle("glis_tile_origin")?
+        };
+        Ok(this)
+    }
+
+    // The program has to be in use
+    fn set(&self, program: &ShaderProgram, values: &BuiltinValues) -> OGLResult<()>
+    {
+        if let Some(handle) = &self.time
+        {
+            program.set(handle, values.time)?
+        }
+        if let Some(handle) = &self.time_hi
+        {
+            program.set(handle, values.time_hi)?
+        }
+        if let Some(handle) = &self.time_lo
+        {
+            program.set(handle, values.time_lo)?
+        }
+        if let Some(handle) = &self.frame
+        {
+            program.set(handle, values.frame)?
+        }
+        if let Some(handle) = &self.resolution
+        {
+            program.set(handle, values.resolution)?
+        }
+        if let Some(handle) = &self.aspect
+        {
+            program.set(handle, values.aspect)?
+        }
+        if let Some(handle) = &self.delta_time
+        {
+            program.set(handle, values.delta_time)?
+        }
+        if let Some(handle) = &self.date
+        {
+            program.set(handle, values.date)?
+        }
+        if let Some(handle) = &self.tile
+        {
+            program.set(handle, values.tile)?
+        }
+        if let Some(handle) = &self.tile_origin
+        {
+            program.set(handle, values.tile_origin)?
+        }
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ComputeMode
+{
+    pub groups: [u32; 3],
+    pub buffer_size: Option<usize>
+}
+
+pub struct Compute
+{
+    pub code: String,
+    pub mode: ComputeMode
+}
+
+// ------------------------------------------------------------
+
+// Runs before the main program each frame, writing into an image the main
+// program samples as `state` and into an optional shader storage buffer
+struct ComputePass
+{
+    program: ShaderProgram,
+    uniforms: BuiltinUniforms,
+    groups: [u32; 3],
+    image: Texture,
+    resolution: [u32; 2],
+    buffer: Option<(ShaderStorageBuffer, usize)>,
+    // After the units of the textures the main program samples
+    sampler_unit: GLenum
+}
+
+impl ComputePass
+{
+    const IMAGE_UNIT: GLuint = 0;
+    const BUFFER_BINDING: GLuint = 0;
+
+    fn new
+    (
+        pointers: &FunctionPointers,
+        compute: &Compute,
+        resolution: [u32; 2],
+        sampler_unit: GLenum
+    ) -> OGLResult<Self>
+    {
+        let program = ShaderProgram::link
+        (
+            pointers,
+            &[
+                &compile_shader
+                (
+                    pointers,
+                    COMPUTE_SHADER,
+                    &compute.code,
+                    "compute"
+                )?
+            ]
+        )?;
+        program.label("compute");
+        let uniforms = BuiltinUniforms::new(&program)?;
+        let image = Texture::new(pointers);
+        image.bind();
+        image.label("compute state");
+        image.setup
+        (
+            None,
+            InterpolationType::Nearest,
+            InterpolationType::Nearest,
+            None
+        );
+        let buffer = compute.mode.buffer_size.map
+        (
+            |size| (ShaderStorageBuffer::new(pointers), size)
+        );
+        let this = Self
+        {
+            program,
+            uniforms,
+            groups: compute.mode.groups,
+            image,
+            resolution,
+            buffer,
+            sampler_unit
+        };
+        this.reset();
+        if let Some((buffer, _)) = &this.buffer
+        {
+            buffer.label("compute storage buffer")
+        }
+        Ok(this)
+    }
+
+    fn reset(&self) -> ()
+    {
+        let zeros = vec![0.0; (self.resolution[0] * self.resolution[1] * 4) as usize];
+        self.image.bind();
+        self.image.fill
+        (
+            Image::<GLfloat>
+            {
+                data: Some(&zeros),
+                resolution: self.resolution,
+                channels: ChannelCount::Four
+            },
+            false
+        );
+        if let Some((buffer, size)) = &self.buffer
+        {
+            buffer.bind();
+            buffer.allocate(*size)
+        }
+    }
+
+    fn dispatch(&self, values: &BuiltinValues) -> OGLResult<()>
+    {
+        let pointers = self.program.pointers();
+        self.program.use_program();
+        self.uniforms.set(&self.program, values)?;
+        self.image.bind_image(Self::IMAGE_UNIT, ImageAccess::ReadWrite, RGBA32F);
+        if let Some((buffer, _)) = &self.buffer
+        {
+            buffer.bind_base(Self::BUFFER_BINDING)
+        }
+        pointers.dispatch_compute(self.groups);
+        pointers.memory_barrier
+        (
+            TEXTURE_FETCH_BARRIER_BIT
+                | SHADER_IMAGE_ACCESS_BARRIER_BIT
+                | SHADER_STORAGE_BARRIER_BIT
+        );
+        pointers.active_texture(self.sampler_unit);
+        self.image.bind();
+        Ok(())
+    }
+}
+
+// ------------------------------------------------------------
+
+// Part of a larger canvas the size of the player's resolution, in pixels
+// from the bottom left corner. Tiles may reach past the canvas edges
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Tile
+{
+    pub origin: [i32; 2],
+    pub canvas: [u32; 2]
+}
+
+// ------------------------------------------------------------
+
 pub struct CanvasPlayer<D>
 {
     timeline: Timeline,
-    canvas: Canvas<D>
+    canvas: Canvas<D>,
+    compute: Option<ComputePass>,
+    uniforms: BuiltinUniforms,
+    text: TextProgram,
+    hud: Option<FrameStats>,
+    notifications: Vec<(String, Instant)>,
+    previous_time: f64,
+    warnings: Vec<String>,
+    tile: Option<Tile>,
+    output: Option<ColorBuffer<GLubyte>>,
+    // Reads are returned calls later so that copies overlap the next frames,
+    // tiles two calls late and the window alpha one
+    tile_readback: ReadbackRing<GLubyte>,
+    alpha_readback: ReadbackRing<GLubyte>
 }
 
 impl<D> CanvasPlayer<D>
@@ -853,19 +2065,85 @@ impl<D> CanvasPlayer<D>
         code: &str,
         textures: Vec<NamedTexture>,
         resolution: [u32; 2],
-        feedback: bool
+        feedback: bool,
+        geometry: &Geometry,
+        compute: Option<&Compute>,
+        hud: bool,
+        presentation: Presentation
     ) -> OGLResult<Self>
     where D: TextureComponentDataType
     {
+        let names: Vec<String> = textures.iter()
+            .map(|texture| texture.name().to_string())
+            .collect();
         let canvas = Canvas::new
         (
             pointers,
             code,
             textures,
             resolution,
-            feedback
+            feedback,
+            geometry,
+            presentation
         )?;
-        Ok(Self{timeline, canvas})
+        let main = canvas.main();
+        let mut warnings = vec!();
+        for name in names.iter().filter(|name| !main.is_active(name))
+        {
+            warnings.push(format!("Texture `{name}` is never used by the shader"))
+        }
+        if feedback && !main.is_active("previous")
+        {
+            warnings.push(String::from("Feedback texture `previous` is never used by the shader"))
+        }
+        // Units go to the feedback texture, the others in order, then the
+        // compute state
+        let samplers = names.len() + feedback as usize + compute.is_some() as usize;
+        let available = pointers.get_integer(MAX_TEXTURE_IMAGE_UNITS);
+        if samplers > available.max(0) as usize
+        {
+            return Err(OGLError::TextureUnits(samplers, available))
+        }
+        let compute = match compute
+        {
+            Some(compute) =>
+            {
+                let unit = samplers - 1;
+                main.use_program();
+                main.set_uniform_if_active("state", unit as GLint)?;
+                Some(ComputePass::new(pointers, compute, resolution, unit as _)?)
+            }
+            None => None
+        };
+        let uniforms = BuiltinUniforms::new(main)?;
+        let hud = match hud
+        {
+            true => Some(FrameStats::new(pointers)),
+            false => None
+        };
+        let text = TextProgram::new(&canvas.vertices)?;
+        let this = Self
+        {
+            timeline,
+            canvas,
+            compute,
+            uniforms,
+            text,
+            hud,
+            notifications: vec!(),
+            previous_time: 0.0,
+            warnings,
+            tile: None,
+            output: None,
+            tile_readback: ReadbackRing::new(pointers, 2),
+            alpha_readback: ReadbackRing::new(pointers, 1)
+        };
+        Ok(this)
+    }
+
+    pub fn warnings(&self) -> &[String]
+    {
+        &self.warnings
     }
 
     pub fn pointers(&self) -> &FunctionPointers
@@ -873,34 +2151,192 @@ impl<D> CanvasPlayer<D>
         self.canvas.pointers()
     }
 
-    pub fn refresh(&mut self) -> bool
+    // Shown at the bottom of the canvas for a few seconds
+    pub fn notify(&mut self, message: &str) -> ()
+    {
+        self.notifications.push((message.to_string(), Instant::now()))
+    }
+
+    fn draw_notifications(&mut self) -> OGLResult<()>
     {
-        match self.timeline.next()
+        const SHOWN: Duration = Duration::from_secs(8);
+        self.notifications.retain(|(_, shown)| shown.elapsed() < SHOWN);
+        if self.notifications.is_empty()
         {
-            Some(frame) =>
+            return Ok(())
+        }
+        let messages: Vec<String> = self.notifications.iter()
+            .map(|(message, _)| message.clone())
+            .collect();
+        let lines = wrap(&messages, NOTIFICATION_GRID);
+        self.text.draw(&lines, NOTIFICATION_GRID, true, self.canvas.resolution())
+    }
+
+    // Frames the timeline is behind on are rendered without being shown,
+    // unless catching up takes long enough to leave the window unresponsive
+    pub fn refresh(&mut self) -> OGLResult<bool>
+    {
+        const CATCH_UP_BUDGET: Duration = Duration::from_millis(250);
+        let start = Instant::now();
+        while let Some(frame) = self.timeline.next()
+        {
+            let time = self.timeline.time();
+            let delta_time = match frame
             {
-                if frame == 0
-                {
-                    if let CanvasKind::Feedback(feedback)
-                        = &mut self.canvas.kind
-                    {
-                        feedback.reset()
-                    }
-                }
-                let time = self.timeline.time();
-                let _ = self.canvas.set_uniform("time", time);
-                let _ = self.canvas.set_uniform("frame", frame as GLint);
-                self.canvas.render();
-                self.canvas.blit([0, 0], COLOR_BUFFER_BIT);
-                true
+                0 => 0.0,
+                _ => (time - self.previous_time).max(0.0) as f32
+            };
+            self.previous_time = time;
+            let present = !self.timeline.behind() || start.elapsed() >= CATCH_UP_BUDGET;
+            self.draw(frame, time, delta_time, present)?;
+            if present
+            {
+                return Ok(true)
+            }
+        }
+        Ok(false)
+    }
+
+    // Renders the frame as the tile of a larger canvas and returns the pixels
+    // of the tile rendered two calls before, rows from the bottom. The
+    // shader sees the coordinates of the full canvas
+    pub fn render_tile(&mut self, tile: Tile, frame: Frame) -> OGLResult<Option<Vec<GLubyte>>>
+    {
+        if self.output.is_none()
+        {
+            let output = ColorBuffer::new(self.pointers(), self.resolution(), false, 1, "tile")?;
+            self.output = Some(output)
+        }
+        self.tile = Some(tile);
+        let time = self.timeline.time_at(frame);
+        let delta_time = match frame
+        {
+            0 => 0.0,
+            _ => (time - self.timeline.time_at(frame - 1)) as f32
+        };
+        self.draw(frame, time, delta_time, true)?;
+        self.output.as_ref().unwrap().framebuffer.bind();
+        self.tile_readback.request([0, 0], self.resolution(), ChannelCount::Four)
+    }
+
+    // Pixels of the tiles still being read back, oldest first
+    pub fn finish_tiles(&mut self) -> OGLResult<Vec<Vec<GLubyte>>>
+    {
+        self.tile_readback.flush()
+    }
+
+    fn draw(&mut self, frame: Frame, time: f64, delta_time: f32, present: bool) -> OGLResult<()>
+    {
+        let start = Instant::now();
+        if frame == 0
+        {
+            if let CanvasKind::Feedback(feedback)
+                = &mut self.canvas.kind
+            {
+                feedback.reset()
+            }
+            if let Some(compute) = &self.compute
+            {
+                compute.reset()
+            }
+        }
+        let [width, height] = self.canvas.render_resolution();
+        // Tiles are given in window pixels, supersampled canvases render more
+        let scale = width / self.canvas.resolution()[0];
+        let (resolution, tile, tile_origin) = match self.tile
+        {
+            Some(Tile{origin, canvas}) =>
+            {
+                let canvas = canvas.map(|size| (size * scale) as f32);
+                let origin = origin.map(|offset| (offset * scale as i32) as f32);
+                (
+                    canvas,
+                    [
+                        origin[0] / canvas[0],
+                        origin[1] / canvas[1],
+                        width as f32 / canvas[0],
+                        height as f32 / canvas[1]
+                    ],
+                    origin
+                )
             }
-            None => false
+            None => ([width as f32, height as f32], [0.0, 0.0, 1.0, 1.0], [0.0; 2])
+        };
+        let [time_hi, time_lo] = split_time(time);
+        let values = BuiltinValues
+        {
+            time: self.timeline.wrap_time(time),
+            time_hi,
+            time_lo,
+            // Kept positive past the range of an int
+            frame: (frame & GLint::MAX as Frame) as _,
+            resolution,
+            aspect: resolution[0] / resolution[1],
+            delta_time,
+            date: Date::now().into(),
+            tile,
+            tile_origin
+        };
+        let mut untimed = None;
+        let hud = match present
+        {
+            true => &mut self.hud,
+            false => &mut untimed
+        };
+        if let Some(compute) = &self.compute
+        {
+            measure(hud, Pass::Compute, || compute.dispatch(&values))?
+        }
+        let main = self.canvas.main();
+        main.use_program();
+        self.uniforms.set(main, &values)?;
+        measure(hud, Pass::Render, || self.canvas.render());
+        if !present
+        {
+            return Ok(())
+        }
+        match &self.output
+        {
+            Some(output) => output.framebuffer.bind(),
+            None => self.pointers().bind_default_framebuffer()
+        }
+        measure
+        (
+            &mut self.hud,
+            Pass::Blit,
+            || self.canvas.blit([0, 0], COLOR_BUFFER_BIT)
+        );
+        if let Some(hud) = &mut self.hud
+        {
+            hud.present(start.elapsed());
+            let lines = hud.lines
+            (
+                self.timeline.fps(),
+                self.timeline.dropped(),
+                self.compute.is_some()
+            );
+            self.text.draw(&lines, HUD_GRID, false, self.canvas.resolution())?
         }
+        self.draw_notifications()
     }
 
     pub fn resolution(&self) -> [u32; 2]
     {
         self.canvas.resolution()
     }
+
+    // Alpha of what was drawn to the window when last called, rows from the
+    // bottom, reading what was just drawn for the next call
+    pub fn alpha(&mut self) -> OGLResult<Option<Vec<u8>>>
+    {
+        let resolution = self.canvas.resolution();
+        self.pointers().bind_default_framebuffer();
+        let pixels = self.alpha_readback.request([0, 0], resolution, ChannelCount::Four)?;
+        Ok
+        (
+            pixels.filter(|pixels| pixels.len() == (resolution[0] * resolution[1] * 4) as usize)
+                .map(|pixels| pixels.chunks_exact(4).map(|pixel| pixel[3]).collect())
+        )
+    }
 }
 
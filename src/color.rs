@@ -0,0 +1,102 @@
+// Transfer functions between stored values and linear light
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transfer
+{
+    Linear,
+    Srgb,
+    Gamma(f32)
+}
+
+impl Transfer
+{
+    pub fn decode(&self, value: f32) -> f32
+    {
+        match self
+        {
+            Self::Linear => value,
+            Self::Srgb => match value <= 0.04045
+            {
+                true => value / 12.92,
+                false => ((value + 0.055) / 1.055).powf(2.4)
+            },
+            Self::Gamma(gamma) => value.max(0.0).powf(*gamma)
+        }
+    }
+
+    // Color channels decoded, alpha is always linear
+    pub fn decode_pixels
+    (
+        &self,
+        values: impl Iterator<Item = f32>,
+        channels: usize
+    ) -> Vec<f32>
+    {
+        let alpha = match channels
+        {
+            2 | 4 => Some(channels - 1),
+            _ => None
+        };
+        values.enumerate().map
+        (
+            |(index, value)| match Some(index % channels) == alpha
+            {
+                true => value,
+                false => self.decode(value)
+            }
+        ).collect()
+    }
+}
+
+// ------------------------------------------------------------
+
+// Compresses float canvases into the displayable range
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tonemap
+{
+    Reinhard,
+    Aces
+}
+
+// ------------------------------------------------------------
+
+// Images are decoded from the input transfer into a linear working space,
+// the canvas is tonemapped, encoded with the output transfer and dithered
+// when it is blitted to the 8-bit window. The defaults pass values through
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ColorSettings
+{
+    pub input: Transfer,
+    pub output: Transfer,
+    pub tonemap: Option<Tonemap>,
+    pub dither: bool
+}
+
+impl Default for ColorSettings
+{
+    fn default() -> Self
+    {
+        Self
+        {
+            input: Transfer::Linear,
+            output: Transfer::Linear,
+            tonemap: None,
+            dither: false
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn transfers() -> ()
+{
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+    assert!(close(Transfer::Srgb.decode(0.5), 0.214));
+    assert!(close(Transfer::Srgb.decode(0.02), 0.02 / 12.92));
+    assert!(close(Transfer::Gamma(2.2).decode(0.5), 0.2176));
+    assert_eq!(Transfer::Linear.decode(0.5), 0.5);
+    let pixels = Transfer::Gamma(2.0).decode_pixels([0.5, 0.5, 0.5, 0.5].into_iter(), 2);
+    assert_eq!(pixels, [0.25, 0.5, 0.25, 0.5]);
+    let pixels = Transfer::Gamma(2.0).decode_pixels([0.5, 0.5, 0.5].into_iter(), 3);
+    assert_eq!(pixels, [0.25; 3])
+}
@@ -5,8 +5,7 @@ use std::
     num::NonZeroU32,
     collections::HashMap
 };
-use super::timeline::*;
-use anyhow::bail;
+use super::{timeline::*, canvas::*, color::*, lexer::*, preprocessor::*, sourcemap::*, shaders::GLSLVersion};
 
 // ------------------------------------------------------------
 
@@ -31,81 +30,88 @@ impl Into<String> for GLSLCode
 
 impl GLSLCode
 {
+    // Non-ASCII characters are only allowed in comments, which are stripped
     fn new(code: &str) -> anyhow::Result<Self>
     {
-        if !code.is_ascii()
+        for token in tokenize(code)?
         {
-            bail!("Non-ASCII characters found")
+            let tokens = match token.kind
+            {
+                TokenKind::Directive => tokenize_directive(code, &token)?,
+                _ => vec!(token)
+            };
+            for token in tokens.iter().filter(|token| token.kind != TokenKind::Comment)
+            {
+                let text = token.text(code);
+                if let Some((index, character)) = text.char_indices()
+                    .find(|(_, character)| !character.is_ascii())
+                {
+                    let start = token.span.start + index;
+                    let span = Span
+                    {
+                        start,
+                        end: start + character.len_utf8(),
+                        line: token.span.line,
+                        column: token.span.column + text[..index].chars().count()
+                    };
+                    let message = format!("Non-ASCII character `{character}` outside of a comment");
+                    return Err(SyntaxError::new(span, message).into())
+                }
+            }
         }
         Ok(Self(code.to_string()))
     }
 
+    // Comments become blanks so lines and columns stay where they were,
+    // trailing blanks are dropped on lines that had a comment. Block comments
+    // end at the first `*/` as in the lexer, they do not nest
     fn strip_comments(&self) -> Self
     {
-        let mut stripped = vec!();
-        let mut stars = 0u32;
-        let mut lines = self.0.lines().peekable();
-        'line: while let Some(line) = lines.next()
+        let mut stripped = String::with_capacity(self.0.len());
+        let mut block = false;
+        let mut commented = false;
+        let mut characters = self.0.chars().peekable();
+        let end_line = |stripped: &mut String, commented: bool|
         {
-            let mut windows = line.as_bytes().windows(2).peekable();
-            if let None = windows.peek()
+            if commented
             {
-                match line.as_bytes()
-                {
-                    [] => continue,
-                    [a] => stripped.push(*a),
-                    _ => unreachable!()
-                }
+                stripped.truncate(stripped.trim_end_matches(' ').len())
             }
-            while let Some(window) = windows.next()
+        };
+        while let Some(character) = characters.next()
+        {
+            match (character, characters.peek())
             {
-                match window
+                ('\n', _) =>
                 {
-                    [47, 47] if stars == 0 =>
-                    {
-                        stripped.push(10);
-                        continue 'line
-                    }
-                    [47, 42] =>
-                    {
-                        windows.nth(0);
-                        stars += 1
-                    }
-                    [42, 47] =>
-                    {
-                        let next = windows.nth(0);
-                        stars = stars.saturating_sub(1);
-                        if stars == 0
-                        {
-                            match next
-                            {
-                                Some([47, b]) => match windows.peek()
-                                {
-                                    Some(_) => continue,
-                                    None => stripped.push(*b)
-                                }
-                                None => stripped.push(10),
-                                _ => unreachable!()
-                            }
-                        }
-                    }
-                    [a, b] if stars == 0 =>
-                    {
-                        stripped.push(*a);
-                        if let None = windows.peek()
-                        {
-                            stripped.push(*b);
-                            if let Some(_) = lines.peek()
-                            {
-                                stripped.push(10)
-                            } 
-                        }
-                    }
-                    _ => {}
+                    end_line(&mut stripped, commented);
+                    stripped.push('\n');
+                    commented = block
+                }
+                ('/', Some('/')) if !block =>
+                {
+                    while characters.next_if(|&next| next != '\n').is_some() {}
+                    commented = true
+                }
+                ('/', Some('*')) if !block =>
+                {
+                    characters.next();
+                    stripped.push_str("  ");
+                    commented = true;
+                    block = true
                 }
+                ('*', Some('/')) if block =>
+                {
+                    characters.next();
+                    stripped.push_str("  ");
+                    block = false
+                }
+                (character, _) if !block => stripped.push(character),
+                _ => stripped.push(' ')
             }
         }
-        Self(String::from_utf8(stripped).unwrap())
+        end_line(&mut stripped, commented);
+        Self(stripped)
     }
 }
 
@@ -118,17 +124,21 @@ fn strip_comments() -> ()
     [
         ["a", "a"],
         ["abc", "abc"],
-        ["abc\n", "abc"], // **
+        ["abc\n", "abc\n"],
         ["abc\ndef", "abc\ndef"],
-        ["abc//def", "abc\n"], // **
+        ["abc//def", "abc"],
         ["abc//def\n", "abc\n"],
         ["abc/*def", "abc"],
-        ["abc//def\nghi//jkl", "abc\nghi\n"], // **
-        ["abc/*def*/ghi", "abcghi"],
-        ["abc/*def\nghi*/jkl", "abcjkl"],
-        ["abc/*/*def*/*/ghi", "abcghi"],
-        ["abc/*//def*/ghi", "abcghi"],
-        ["/**/a", "a"]
+        ["abc//def\nghi//jkl", "abc\nghi"],
+        ["abc/*def*/ghi", "abc       ghi"],
+        ["abc/*def\nghi*/jkl", "abc\n     jkl"],
+        // Block comments do not nest, the first `*/` ends both
+        ["abc/*/*def*/*/ghi", "abc         */ghi"],
+        ["/* see /* here */ code", "                  code"],
+        ["abc/*//def*/ghi", "abc         ghi"],
+        ["/**/a", "    a"],
+        ["a /* b */\n c", "a\n c"],
+        ["a/* é\n— */b//ü\nc", "a\n    b\nc"]
     ]
     {
         let code = String::from(input);
@@ -140,12 +150,637 @@ fn strip_comments() -> ()
 
 // ------------------------------------------------------------
 
+pub const BUILTIN_UNIFORMS: [(&str, &str); 8] =
+[
+    ("float", "time"),
+    ("float", "time_hi"),
+    ("float", "time_lo"),
+    ("int", "frame"),
+    ("vec2", "resolution"),
+    ("float", "aspect"),
+    ("float", "delta_time"),
+    ("vec4", "date")
+];
+
+// Whether any declaration statement carries both the qualifier and the name
+fn declares(code: &str, qualifier: &str, name: &str) -> bool
+{
+    code.split([';', '{', '}']).any
+    (
+        |statement|
+        {
+            let mut tokens = statement
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .filter(|token| !token.is_empty());
+            tokens.clone().any(|token| token == qualifier)
+                && tokens.any(|token| token == name)
+        }
+    )
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pipeline
+{
+    Quad,
+    Vertices,
+    Mesh
+}
+
+// Sources without a #version directive get the boilerplate injected.
+// Compute shaders need 4.30 and none of the stage interface declarations
+fn inject_prelude(code: &str, pipeline: Pipeline, compute: bool) -> String
+{
+    if GLSLVersion::of(code).is_some()
+    {
+        return code.to_string()
+    }
+    let mut prelude = String::from
+    (
+        match compute
+        {
+            true => "#version 430 core\n",
+            false => "#version 330 core\n"
+        }
+    );
+    for (kind, name) in BUILTIN_UNIFORMS
+    {
+        if !declares(code, "uniform", name)
+        {
+            prelude += &format!("uniform {kind} {name};\n")
+        }
+    }
+    if compute
+    {
+        prelude += "#ifndef COMPUTE\n"
+    }
+    match pipeline
+    {
+        Pipeline::Vertices =>
+        {
+            if !declares(code, "uniform", "vertex_count")
+            {
+                prelude += "uniform float vertex_count;\n"
+            }
+            if !declares(code, "out", "v_color")
+            {
+                prelude += "out vec4 v_color;\n"
+            }
+        }
+        Pipeline::Mesh =>
+        {
+            for name in ["bounds_min", "bounds_max"]
+            {
+                if !declares(code, "uniform", name)
+                {
+                    prelude += &format!("uniform vec3 {name};\n")
+                }
+            }
+        }
+        Pipeline::Quad =>
+        {
+            if !declares(code, "in", "st") && !declares(code, "varying", "st")
+            {
+                prelude += "in vec2 st;\n"
+            }
+            if !code.split([';', '{', '}']).any
+            (
+                |statement| statement.split_ascii_whitespace().next() == Some("out")
+            )
+            {
+                prelude += "out vec4 color;\n"
+            }
+        }
+    }
+    if compute
+    {
+        prelude += "#endif\n"
+    }
+    prelude + code
+}
+
+// Line index where the stage macro goes, right after the #version directive
+fn stage_index(code: &str) -> usize
+{
+    code.lines().position
+    (
+        |line| line.trim_start()
+            .strip_prefix('#')
+            .is_some_and(|rest| rest.trim_start().starts_with("version"))
+    ).map_or(0, |index| index + 1)
+}
+
+// Line after the leading `#version` and `#extension` directives and the
+// conditionals around them, declarations can only follow those
+fn declarations_index(code: &str) -> usize
+{
+    let mut index = stage_index(code);
+    let mut depth = 0usize;
+    let mut pending = false;
+    for (number, line) in code.lines().enumerate()
+    {
+        let line = line.trim();
+        let directive = match line.strip_prefix('#')
+        {
+            Some(rest) => rest.trim_start(),
+            None if line.is_empty() || line.starts_with("//") => continue,
+            None => break
+        };
+        let name = directive.split(|character: char| !character.is_alphanumeric())
+            .next()
+            .unwrap_or_default();
+        match name
+        {
+            "if" | "ifdef" | "ifndef" => depth += 1,
+            "endif" => depth = depth.saturating_sub(1),
+            "version" | "extension" => pending = true,
+            _ => {}
+        }
+        if pending && depth == 0
+        {
+            index = index.max(number + 1);
+            pending = false
+        }
+    }
+    index
+}
+
+// Makes a stage macro visible to the code, one line is injected
+fn define_stage(code: &str, name: &str) -> String
+{
+    let mut lines: Vec<&str> = code.lines().collect();
+    let define = format!("#define {name}");
+    lines.insert(stage_index(code), &define);
+    lines.join("\n")
+}
+
+// Macros each stage is compiled with
+const STAGES: [&str; 3] = ["VERTEX", "FRAGMENT", "COMPUTE"];
+
+// Error at the directive that defines the parameter
+fn directive_error(preprocessed: &Preprocessed, parameter: &str, message: String) -> anyhow::Error
+{
+    match preprocessed.define(parameter)
+    {
+        Some(define) => anyhow::anyhow!("{}: {message}", define.span),
+        None => anyhow::anyhow!(message)
+    }
+}
+
+fn parse_vertex_mode(code: &Preprocessed) -> anyhow::Result<Option<VertexMode>>
+{
+    let fail = |parameter: &str, message: String| Err(directive_error(code, parameter, message));
+    let count = match code.value("vertices")
+    {
+        Some(value) => match value.parse::<NonZeroU32>()
+        {
+            Ok(count) => count.get(),
+            Err(error) => return fail
+                ("vertices", format!("Could not parse 'vertices' directive: {error}"))
+        }
+        None =>
+        {
+            for parameter in ["primitive", "depth_test", "blending"]
+            {
+                if code.define(parameter).is_some()
+                {
+                    return fail
+                    (
+                        parameter,
+                        format!("The '{parameter}' directive requires a 'vertices' directive")
+                    )
+                }
+            }
+            return Ok(None)
+        }
+    };
+    let primitive = match code.value("primitive")
+    {
+        None | Some("points") => Primitive::Points,
+        Some("lines") => Primitive::Lines,
+        Some("line_strip") => Primitive::LineStrip,
+        Some("line_loop") => Primitive::LineLoop,
+        Some("triangles") => Primitive::Triangles,
+        Some("triangle_strip") => Primitive::TriangleStrip,
+        Some("triangle_fan") => Primitive::TriangleFan,
+        Some(value) => return fail("primitive", format!("Unknown primitive '{value}'"))
+    };
+    let blending = match code.value("blending")
+    {
+        None => None,
+        Some("" | "alpha") => Some(Blending::Alpha),
+        Some("additive") => Some(Blending::Additive),
+        Some("premultiplied") => Some(Blending::Premultiplied),
+        Some(value) => return fail("blending", format!("Unknown blending mode '{value}'"))
+    };
+    let mode = VertexMode
+    {
+        count,
+        primitive,
+        depth: code.define("depth_test").is_some(),
+        blending
+    };
+    Ok(Some(mode))
+}
+
+fn parse_compute_mode(code: &Preprocessed) -> anyhow::Result<Option<ComputeMode>>
+{
+    let fail = |parameter: &str, message: String| Err(directive_error(code, parameter, message));
+    let groups = match code.value("compute")
+    {
+        Some(value) =>
+        {
+            let values = match value.split_ascii_whitespace()
+                .map(str::parse::<NonZeroU32>)
+                .collect::<Result<Vec<NonZeroU32>, _>>()
+            {
+                Ok(values) if (1..=3).contains(&values.len()) => values,
+                Ok(_) => return fail
+                    ("compute", String::from("Expected 1 to 3 values for the 'compute' directive")),
+                Err(error) => return fail
+                    ("compute", format!("Could not parse 'compute' directive: {error}"))
+            };
+            let mut groups = [1; 3];
+            for (group, value) in groups.iter_mut().zip(values)
+            {
+                *group = value.get()
+            }
+            groups
+        }
+        None =>
+        {
+            if code.define("storage_buffer").is_some()
+            {
+                return fail
+                (
+                    "storage_buffer",
+                    String::from("The 'storage_buffer' directive requires a 'compute' directive")
+                )
+            }
+            return Ok(None)
+        }
+    };
+    let buffer_size = match code.value("storage_buffer")
+    {
+        Some(value) => match value.parse::<NonZeroU32>()
+        {
+            Ok(size) => Some(size.get() as usize),
+            Err(error) => return fail
+                ("storage_buffer", format!("Could not parse 'storage_buffer' directive: {error}"))
+        }
+        None => None
+    };
+    Ok(Some(ComputeMode{groups, buffer_size}))
+}
+
+fn parse_color_settings(code: &Preprocessed) -> anyhow::Result<ColorSettings>
+{
+    let fail = |parameter: &str, message: String| Err(directive_error(code, parameter, message));
+    let mut settings = ColorSettings::default();
+    for parameter in ["color_input", "color_output"]
+    {
+        let value = match code.value(parameter)
+        {
+            Some(value) => value,
+            None => continue
+        };
+        let transfer = match value.split_ascii_whitespace().collect::<Vec<&str>>()[..]
+        {
+            ["linear"] => Transfer::Linear,
+            ["srgb"] => Transfer::Srgb,
+            ["gamma"] => Transfer::Gamma(2.2),
+            ["gamma", gamma] => match gamma.parse::<f32>()
+            {
+                Ok(gamma) if gamma.is_finite() && gamma > 0.0 => Transfer::Gamma(gamma),
+                _ => return fail(parameter, format!("Invalid gamma '{gamma}'"))
+            }
+            _ => return fail(parameter, format!("Unknown transfer function '{value}'"))
+        };
+        match parameter
+        {
+            "color_input" => settings.input = transfer,
+            _ => settings.output = transfer
+        }
+    }
+    settings.tonemap = match code.value("tonemap")
+    {
+        None => None,
+        Some("" | "reinhard") => Some(Tonemap::Reinhard),
+        Some("aces") => Some(Tonemap::Aces),
+        Some(value) => return fail("tonemap", format!("Unknown tonemapping operator '{value}'"))
+    };
+    settings.dither = code.define("dither").is_some();
+    Ok(settings)
+}
+
+fn parse_stepping(code: &Preprocessed) -> anyhow::Result<Stepping>
+{
+    let value = match code.value("timing")
+    {
+        Some(value) => value,
+        None => return Ok(Stepping::RealTime)
+    };
+    let stepping = match value.split_ascii_whitespace().collect::<Vec<&str>>()[..]
+    {
+        ["realtime"] => Stepping::RealTime,
+        ["fixed"] => Stepping::Fixed,
+        ["catchup"] => Stepping::CatchUp(None),
+        ["catchup", limit] => match limit.parse::<NonZeroU32>()
+        {
+            Ok(limit) => Stepping::CatchUp(Some(limit.get())),
+            Err(error) => return Err(directive_error
+                (code, "timing", format!("Could not parse the catch-up limit '{limit}': {error}")))
+        }
+        _ => return Err(directive_error(code, "timing", format!("Unknown timing '{value}'")))
+    };
+    Ok(stepping)
+}
+
+fn parse_time_period(code: &Preprocessed) -> anyhow::Result<Option<f64>>
+{
+    match code.value("time_period")
+    {
+        None => Ok(None),
+        Some(value) => match value.parse::<f64>()
+        {
+            Ok(period) if period.is_finite() && period > 0.0 => Ok(Some(period)),
+            _ => Err(directive_error
+                (code, "time_period", format!("Expected a positive number of seconds, got '{value}'")))
+        }
+    }
+}
+
+fn parse_antialiasing(code: &Preprocessed) -> anyhow::Result<Antialiasing>
+{
+    let fail = |parameter: &str, message: String| Err(directive_error(code, parameter, message));
+    let mut antialiasing = Antialiasing::default();
+    if let Some(value) = code.value("supersample")
+    {
+        let (factor, downsampling) = match value.split_ascii_whitespace().collect::<Vec<&str>>()[..]
+        {
+            [factor] | [factor, "box"] => (factor, Downsampling::Box),
+            [factor, "lanczos"] => (factor, Downsampling::Lanczos),
+            _ => return fail
+                ("supersample", format!("Expected a factor and 'box' or 'lanczos', got '{value}'"))
+        };
+        antialiasing.supersampling = match factor.parse::<u32>()
+        {
+            Ok(factor) if (1..=4).contains(&factor) => factor,
+            _ => return fail
+                ("supersample", format!("The supersampling factor must be 1 to 4, got '{factor}'"))
+        };
+        antialiasing.downsampling = downsampling
+    }
+    if let Some(value) = code.value("msaa")
+    {
+        antialiasing.samples = match value.parse::<u32>()
+        {
+            Ok(samples) if [2, 4, 8, 16].contains(&samples) => samples,
+            _ => return fail
+                ("msaa", format!("The sample count must be 2, 4, 8 or 16, got '{value}'"))
+        }
+    }
+    Ok(antialiasing)
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn prelude() -> ()
+{
+    let code = "uniform float time;\nvoid main(){color = vec4(st, time, 1.0);}";
+    let injected = inject_prelude(code, Pipeline::Quad, false);
+    assert!(injected.starts_with("#version 330 core\n"));
+    assert!(injected.ends_with(code));
+    assert_eq!(injected.matches("uniform float time;").count(), 1);
+    for declaration in
+    [
+        "uniform int frame;",
+        "uniform vec2 resolution;",
+        "in vec2 st;",
+        "out vec4 color;"
+    ]
+    {
+        assert!(injected.contains(declaration))
+    }
+    let code = "void main(){gl_Position = vec4(0.0); v_color = vec4(1.0);}";
+    let injected = inject_prelude(code, Pipeline::Vertices, false);
+    assert!(injected.contains("uniform float vertex_count;\nout vec4 v_color;\n"));
+    assert!(!injected.contains("in vec2 st;"));
+    let code = "#version 120\nvoid main(){}";
+    assert_eq!(inject_prelude(code, Pipeline::Quad, false), code);
+    let code = "void main(){}";
+    let injected = inject_prelude(code, Pipeline::Quad, true);
+    assert!(injected.starts_with("#version 430 core\n"));
+    assert!(injected.contains("#ifndef COMPUTE\nin vec2 st;\nout vec4 color;\n#endif\n"));
+    for (code, version) in
+    [
+        ("void main(){}", "330"),
+        ("#define compute 8 8\nvoid main(){}", "430"),
+        ("#version 300 es\nprecision highp float;\nout vec4 color;\nvoid main(){}", "300 es"),
+        ("#version 100\nvoid main(){gl_FragColor = vec4(1.0);}", "100 es")
+    ]
+    {
+        assert_eq!(AnnotatedGLSL::new(code).unwrap().version().to_string(), version)
+    }
+    let code = "#define no_prelude\nout vec4 color;\nvoid main(){color = vec4(1.0);}";
+    let annotated = AnnotatedGLSL::new(code).unwrap();
+    assert_eq!(annotated.code(), code);
+    assert_eq!(annotated.source_map().original(2), Some(2))
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn stages() -> ()
+{
+    let code = "// header\n#version 330 core\nvoid main(){}";
+    assert_eq!
+    (
+        define_stage(code, "VERTEX"),
+        "// header\n#version 330 core\n#define VERTEX\nvoid main(){}"
+    );
+    let code = "#version 120\nvoid main(){}";
+    assert_eq!
+    (
+        define_stage(code, "FRAGMENT"),
+        "#version 120\n#define FRAGMENT\nvoid main(){}"
+    );
+    let code = "a\n  #mesh  bunny 1.obj \nb";
+    let preprocessed = preprocess(code, &STAGES).unwrap();
+    let blanked = preprocessed.blank(code);
+    assert_eq!(blanked.lines().map(str::trim).collect::<Vec<&str>>(), ["a", "", "b"]);
+    assert_eq!(preprocessed.mesh.unwrap().0, "bunny 1.obj");
+    assert!(preprocess("#mesh", &STAGES).is_err());
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn vertex_mode() -> ()
+{
+    let parse_vertex_mode = |code: &str| parse_vertex_mode(&preprocess(code, &STAGES).unwrap());
+    assert_eq!(parse_vertex_mode("void main(){}").unwrap(), None);
+    assert_eq!
+    (
+        parse_vertex_mode
+        (
+            "
+            #define vertices 10000
+            #define primitive line_strip
+            #define depth_test
+            #define blending additive
+            "
+        ).unwrap(),
+        Some
+        (
+            VertexMode
+            {
+                count: 10000,
+                primitive: Primitive::LineStrip,
+                depth: true,
+                blending: Some(Blending::Additive)
+            }
+        )
+    );
+    let mode = parse_vertex_mode("#define vertices 3\n#define blending")
+        .unwrap().unwrap();
+    assert_eq!(mode.primitive, Primitive::Points);
+    assert_eq!(mode.blending, Some(Blending::Alpha));
+    assert!(!mode.depth);
+    for invalid in
+    [
+        "#define vertices 0",
+        "#define vertices 3\n#define primitive quads",
+        "#define primitive triangles"
+    ]
+    {
+        parse_vertex_mode(invalid).unwrap_err();
+    }
+}
+
+#[test]
+fn compute_mode() -> ()
+{
+    let parse_compute_mode = |code: &str| parse_compute_mode(&preprocess(code, &STAGES).unwrap());
+    assert_eq!(parse_compute_mode("void main(){}").unwrap(), None);
+    assert_eq!
+    (
+        parse_compute_mode("#define compute 64 32\n#define storage_buffer 4096")
+            .unwrap(),
+        Some(ComputeMode{groups: [64, 32, 1], buffer_size: Some(4096)})
+    );
+    for invalid in
+    [
+        "#define compute",
+        "#define compute 1 2 3 4",
+        "#define compute 0",
+        "#define compute 8\n#define storage_buffer none",
+        "#define storage_buffer 16"
+    ]
+    {
+        parse_compute_mode(invalid).unwrap_err();
+    }
+}
+
+#[test]
+fn color_settings() -> ()
+{
+    let parse = |code: &str| parse_color_settings(&preprocess(code, &STAGES).unwrap());
+    assert_eq!(parse("void main(){}").unwrap(), ColorSettings::default());
+    assert_eq!
+    (
+        parse("#define color_input srgb\n#define color_output gamma 2.4\n#define tonemap aces\n#define dither")
+            .unwrap(),
+        ColorSettings
+        {
+            input: Transfer::Srgb,
+            output: Transfer::Gamma(2.4),
+            tonemap: Some(Tonemap::Aces),
+            dither: true
+        }
+    );
+    assert_eq!(parse("#define color_output gamma").unwrap().output, Transfer::Gamma(2.2));
+    assert_eq!(parse("#define tonemap").unwrap().tonemap, Some(Tonemap::Reinhard));
+    for invalid in
+    [
+        "#define color_output rec709",
+        "#define color_input gamma -1",
+        "#define color_output gamma 2.2 2.4",
+        "#define tonemap filmic"
+    ]
+    {
+        parse(invalid).unwrap_err();
+    }
+}
+
+#[test]
+fn stepping() -> ()
+{
+    let parse = |code: &str| parse_stepping(&preprocess(code, &STAGES).unwrap());
+    assert_eq!(parse("void main(){}").unwrap(), Stepping::RealTime);
+    assert_eq!(parse("#define timing fixed").unwrap(), Stepping::Fixed);
+    assert_eq!(parse("#define timing catchup").unwrap(), Stepping::CatchUp(None));
+    assert_eq!(parse("#define timing catchup 10").unwrap(), Stepping::CatchUp(Some(10)));
+    for invalid in ["#define timing", "#define timing catchup 0", "#define timing steady"]
+    {
+        parse(invalid).unwrap_err();
+    }
+    let parse = |code: &str| parse_time_period(&preprocess(code, &STAGES).unwrap());
+    assert_eq!(parse("void main(){}").unwrap(), None);
+    assert_eq!(parse("#define time_period 3600").unwrap(), Some(3600.0));
+    for invalid in ["#define time_period", "#define time_period 0", "#define time_period inf"]
+    {
+        parse(invalid).unwrap_err();
+    }
+}
+
+#[test]
+fn antialiasing() -> ()
+{
+    let parse = |code: &str| parse_antialiasing(&preprocess(code, &STAGES).unwrap());
+    assert_eq!(parse("void main(){}").unwrap(), Antialiasing::default());
+    assert_eq!
+    (
+        parse("#define supersample 3 lanczos
+#define msaa 4").unwrap(),
+        Antialiasing
+        {
+            supersampling: 3,
+            downsampling: Downsampling::Lanczos,
+            samples: 4
+        }
+    );
+    assert_eq!(parse("#define supersample 2").unwrap().downsampling, Downsampling::Box);
+    for invalid in
+    [
+        "#define supersample",
+        "#define supersample 8",
+        "#define supersample 2 bicubic",
+        "#define msaa 3",
+        "#define msaa"
+    ]
+    {
+        parse(invalid).unwrap_err();
+    }
+}
+
+// ------------------------------------------------------------
+
 pub struct CodeAnnotations
 {
     resolution: [u32; 2],
     feedback: bool,
     rate: FPS,
     range: FrameRange,
+    stepping: Stepping,
+    time_period: Option<f64>,
+    vertex: Option<VertexMode>,
+    mesh_path: Option<PathBuf>,
+    compute: Option<ComputeMode>,
+    hud: bool,
+    compositing: Compositing,
+    color: ColorSettings,
+    antialiasing: Antialiasing,
     texture_paths: HashMap<String, PathBuf>
 }
 
@@ -159,6 +794,15 @@ impl Default for CodeAnnotations
             feedback: Default::default(),
             rate: Default::default(),
             range: Default::default(),
+            stepping: Default::default(),
+            time_period: Default::default(),
+            vertex: Default::default(),
+            mesh_path: Default::default(),
+            compute: Default::default(),
+            hud: Default::default(),
+            compositing: Default::default(),
+            color: Default::default(),
+            antialiasing: Default::default(),
             texture_paths: Default::default()
         }
     }
@@ -186,6 +830,51 @@ impl CodeAnnotations
         self.range
     }
 
+    pub fn stepping(&self) -> Stepping
+    {
+        self.stepping
+    }
+
+    pub fn time_period(&self) -> Option<f64>
+    {
+        self.time_period
+    }
+
+    pub fn vertex(&self) -> Option<VertexMode>
+    {
+        self.vertex
+    }
+
+    pub fn mesh_path(&self) -> Option<&PathBuf>
+    {
+        self.mesh_path.as_ref()
+    }
+
+    pub fn compute(&self) -> Option<ComputeMode>
+    {
+        self.compute
+    }
+
+    pub fn hud(&self) -> bool
+    {
+        self.hud
+    }
+
+    pub fn compositing(&self) -> Compositing
+    {
+        self.compositing
+    }
+
+    pub fn color(&self) -> ColorSettings
+    {
+        self.color
+    }
+
+    pub fn antialiasing(&self) -> Antialiasing
+    {
+        self.antialiasing
+    }
+
     pub fn texture_paths(&self) -> &HashMap<String, PathBuf>
     {
         &self.texture_paths
@@ -197,51 +886,33 @@ impl CodeAnnotations
 pub struct AnnotatedGLSL
 {
     code: String,
-    annotations: CodeAnnotations
+    vertex_code: Option<String>,
+    compute_code: Option<String>,
+    annotations: CodeAnnotations,
+    source_map: SourceMap
 }
 
 impl AnnotatedGLSL
 {
     pub fn new(code: &str) -> anyhow::Result<Self>
     {
-        let code = GLSLCode::new(code)?;
-        let code: String = code.strip_comments().into();
+        let preprocessed = preprocess(code, &STAGES)?;
         let mut annotations = CodeAnnotations::default();
-        let mut to_strip = vec!();
-        for (index, substring) in code.match_indices("uniform sampler2D ")
+        for sampler in &preprocessed.samplers
         {
-            let start = index + substring.len();
-            let mut at = start;
-            for character in code[start..].chars()
+            if let Some((path, _)) = &sampler.path
             {
-                match character
-                {
-                    ';' => break,
-                    '@' =>
-                    {
-                        let name = code[start..at].trim();
-                        if let Some(mut end) = code[at + 1..].find(";")
-                        {
-                            end += at;
-                            let path = PathBuf::from(code[at + 1..=end].trim());
-                            annotations.texture_paths
-                                .insert(name.to_string(), path);
-                            to_strip.extend(at..=end)
-                        }
-                    }
-                    _ => at += 1
-                }
+                annotations.texture_paths
+                    .insert(sampler.name.clone(), PathBuf::from(path));
             }
         }
-        annotations.feedback = code
-            .find("uniform sampler2D previous")
-            .is_some();
+        annotations.feedback = preprocessed.samplers.iter()
+            .any(|sampler| sampler.name == "previous");
+        let fail = |parameter: &str, message: String|
+            Err(directive_error(&preprocessed, parameter, message));
         for parameter in ["size", "rate", "loop"]
         {
-            let prefix = format!("#define {parameter} ");
-            let value = code.lines().map(str::trim)
-                .filter(|l| l.starts_with(&prefix)).last()
-                .map(|l| l[prefix.len()..].trim());
+            let value = preprocessed.value(parameter);
             match (value, parameter)
             {
                 (None, "loop") => {},
@@ -256,8 +927,8 @@ impl AnnotatedGLSL
                             FPS(value),
                         _ => unreachable!()
                     }
-                    Err(error) => bail!
-                        (format!("Could not parse '{parameter}' directive: {error}"))
+                    Err(error) => return fail
+                        (parameter, format!("Could not parse '{parameter}' directive: {error}"))
                 }
                 (Some(value), "size") => match 
                     value.split_ascii_whitespace().map(str::parse::<u32>)
@@ -266,22 +937,102 @@ impl AnnotatedGLSL
                     Ok(values) if values.len() == 2 =>
                         annotations.resolution =
                             values.try_into().unwrap(),
-                    Ok(_) => bail!
-                        (format!("Expected 2 values for the '{parameter}' directive")),
-                    Err(error) => bail!
-                        (format!("Could not parse '{parameter}' directive: {error}"))
+                    Ok(_) => return fail
+                        (parameter, format!("Expected 2 values for the '{parameter}' directive")),
+                    Err(error) => return fail
+                        (parameter, format!("Could not parse '{parameter}' directive: {error}"))
                 }
                 (None, "rate") => {}
                 (None, "size") => {}
                 _ => unreachable!()
             }
         }
-        let code = code.into_bytes().iter().enumerate()
-            .filter(|(i, _)| !to_strip.contains(i))
-            .map(|(_, c)| *c).collect::<Vec<u8>>();
-        let code = std::str::from_utf8(&code)
-            .unwrap().to_string();
-        Ok(Self{code, annotations})
+        annotations.stepping = parse_stepping(&preprocessed)?;
+        annotations.time_period = parse_time_period(&preprocessed)?;
+        annotations.mesh_path = preprocessed.mesh.as_ref()
+            .map(|(path, _)| PathBuf::from(path));
+        annotations.vertex = parse_vertex_mode(&preprocessed)?;
+        annotations.compute = parse_compute_mode(&preprocessed)?;
+        annotations.hud = preprocessed.define("hud").is_some();
+        annotations.color = parse_color_settings(&preprocessed)?;
+        annotations.antialiasing = parse_antialiasing(&preprocessed)?;
+        annotations.compositing = match
+        (
+            preprocessed.define("overlay").is_some(),
+            preprocessed.define("checkerboard").is_some()
+        )
+        {
+            (true, true) => return fail
+            (
+                "overlay",
+                String::from("The 'overlay' and 'checkerboard' directives are mutually exclusive")
+            ),
+            (true, false) => Compositing::Overlay,
+            (false, true) => Compositing::Checkerboard,
+            (false, false) => Compositing::Opaque
+        };
+        let code: String = GLSLCode::new(&preprocessed.blank(code))?
+            .strip_comments()
+            .into();
+        let pipeline = match (&annotations.vertex, &annotations.mesh_path)
+        {
+            (Some(_), Some(_)) => return fail
+            (
+                "vertices",
+                String::from("The 'vertices' and 'mesh' directives are mutually exclusive")
+            ),
+            (Some(_), None) => Pipeline::Vertices,
+            (None, Some(_)) => Pipeline::Mesh,
+            (None, None) => Pipeline::Quad
+        };
+        let compute = annotations.compute.is_some();
+        let mut source_map = SourceMap::new(&code);
+        // The prelude can be opted out of to write every declaration by hand
+        let injected = match preprocessed.define("no_prelude")
+        {
+            Some(_) => code.clone(),
+            None => inject_prelude(&code, pipeline, compute)
+        };
+        source_map.inject(0, injected.lines().count() - code.lines().count());
+        let code = injected;
+        if compute || pipeline == Pipeline::Mesh
+        {
+            source_map.inject(stage_index(&code), 1)
+        }
+        let compute_code = match compute
+        {
+            true => Some(define_stage(&code, "COMPUTE")),
+            false => None
+        };
+        let this = match (pipeline, compute)
+        {
+            (Pipeline::Mesh, _) => Self
+            {
+                vertex_code: Some(define_stage(&code, "VERTEX")),
+                code: define_stage(&code, "FRAGMENT"),
+                compute_code,
+                annotations,
+                source_map
+            },
+            (Pipeline::Vertices, true) => Self
+            {
+                code: define_stage(&code, "VERTEX"),
+                vertex_code: None,
+                compute_code,
+                annotations,
+                source_map
+            },
+            (Pipeline::Quad, true) => Self
+            {
+                code: define_stage(&code, "FRAGMENT"),
+                vertex_code: None,
+                compute_code,
+                annotations,
+                source_map
+            },
+            _ => Self{code, vertex_code: None, compute_code, annotations, source_map}
+        };
+        Ok(this)
     }
 
     pub fn code(&self) -> &str
@@ -289,15 +1040,68 @@ impl AnnotatedGLSL
         &self.code
     }
 
+    pub fn vertex_code(&self) -> Option<&str>
+    {
+        self.vertex_code.as_deref()
+    }
+
+    pub fn compute_code(&self) -> Option<&str>
+    {
+        self.compute_code.as_deref()
+    }
+
     pub fn annotations(&self) -> &CodeAnnotations
     {
         &self.annotations
     }
+
+    pub fn source_map(&self) -> &SourceMap
+    {
+        &self.source_map
+    }
+
+    // Shared by all stages, sources without a directive get one injected
+    pub fn version(&self) -> GLSLVersion
+    {
+        GLSLVersion::of(&self.code).unwrap_or_default()
+    }
+
+    // Fragment coordinates are offset to the tile of a larger canvas, shaders
+    // depending on the whole canvas cannot be split
+    pub fn into_tiled(self) -> anyhow::Result<Self>
+    {
+        let annotations = &self.annotations;
+        if annotations.feedback()
+        {
+            anyhow::bail!("Feedback shaders cannot be rendered in tiles, each frame samples the whole previous one")
+        }
+        if annotations.compute().is_some()
+        {
+            anyhow::bail!("Compute shaders cannot be rendered in tiles, their state spans the whole canvas")
+        }
+        if annotations.vertex().is_some() || annotations.mesh_path().is_some()
+        {
+            anyhow::bail!("Only shaders drawn on the full-screen quad can be rendered in tiles")
+        }
+        const TILE_ORIGIN: &str = "\
+            #ifdef GL_ES\n\
+            uniform highp vec2 glis_tile_origin;\n\
+            #else\n\
+            uniform vec2 glis_tile_origin;\n\
+            #endif\n\
+            #define gl_FragCoord (gl_FragCoord + vec4(glis_tile_origin, 0.0, 0.0))";
+        let index = declarations_index(&self.code);
+        let mut lines: Vec<&str> = self.code.lines().collect();
+        lines.insert(index, TILE_ORIGIN);
+        let code = lines.join("\n");
+        let mut source_map = self.source_map;
+        source_map.inject(index, TILE_ORIGIN.lines().count());
+        Ok(Self{code, source_map, ..self})
+    }
 }
 
 // ------------------------------------------------------------
 
-// uniform\nsampler2D will not work, which is valid GLSL
 #[test]
 fn parse() -> ()
 {
@@ -321,7 +1125,12 @@ fn parse() -> ()
         uniform sampler2D a @ imag/**/es/a.jpg;
         uniform sampler2D b @ b.jpg;
         // uniform sampler2D c @ c.jpg;
-        uniform sampler/**/2D d @ d d/**/;
+        uniform/**/sampler2D d @ d d/**/;
+        uniform
+        sampler2D f @ \"f f.png\";
+        #ifdef unused
+        uniform sampler2D g @ g.png;
+        #endif
         uniform sampler2D 
             e
             @/**/
@@ -339,7 +1148,7 @@ fn parse() -> ()
             => assert_eq!(end.get(), 100),
         _ => panic!()
     }
-    assert_eq!(texture_paths.len(), 4);
+    assert_eq!(texture_paths.len(), 5);
     for (key, value) in texture_paths
     {
         let value = value.to_str().unwrap();
@@ -349,8 +1158,94 @@ fn parse() -> ()
             "b" => assert_eq!(value, "b.jpg"),
             "d" => assert_eq!(value, "d d"),
             "e" => assert_eq!(value, "e"),
+            "f" => assert_eq!(value, "f f.png"),
             _ => unreachable!()
         }
     }
 }
 
+#[test]
+fn spans() -> ()
+{
+    for (code, message) in
+    [
+        ("void main(){}\n  #define rate x", "line 2, column 3: Could not parse 'rate'"),
+        ("#define vertices 3\n\n#define primitive quads", "line 3, column 1: Unknown primitive"),
+        ("#define checkerboard\n #define overlay", "line 2, column 2: The 'overlay' and 'checkerboard'"),
+        ("uniform sampler2D a @;", "line 1, column 21: Expected a texture path"),
+        ("#ifdef VERTEX\nvoid main(){}", "line 1, column 1: Unterminated conditional"),
+        ("// é\nfloat é = 1.0;", "line 2, column 7: Non-ASCII character `é`"),
+        ("#define a 1 /* é */ ü", "line 1, column 21: Non-ASCII character `ü`")
+    ]
+    {
+        let error = AnnotatedGLSL::new(code).err().unwrap().to_string();
+        assert!(error.starts_with(message), "{error}")
+    }
+}
+
+#[test]
+fn unicode() -> ()
+{
+    let code = AnnotatedGLSL::new
+    (
+        "// Naïve — shader\nuniform sampler2D a @ textures/café.png;\n/* ü */void main(){}"
+    ).unwrap();
+    assert!(code.code().is_ascii());
+    assert_eq!
+    (
+        code.annotations().texture_paths()["a"].to_str().unwrap(),
+        "textures/café.png"
+    )
+}
+
+#[test]
+fn lines() -> ()
+{
+    let source = "/* a\n b */ #define rate 30\n#define compute 8 8\nfloat x; // c\nvoid main(){}";
+    let code = AnnotatedGLSL::new(source).unwrap();
+    for stage in [code.code(), code.compute_code().unwrap()]
+    {
+        let line = stage.lines().position(|line| line == "float x;").unwrap() + 1;
+        assert_eq!(code.source_map().original(line), Some(4));
+        assert_eq!(code.source_map().original(1), None)
+    }
+}
+
+#[test]
+fn tiled() -> ()
+{
+    let code = AnnotatedGLSL::new("float x;\nvoid main(){color = gl_FragCoord;}").unwrap()
+        .into_tiled()
+        .unwrap();
+    let line = code.code().lines().position(|line| line == "float x;").unwrap() + 1;
+    assert_eq!(code.source_map().original(line), Some(1));
+    assert!(code.code().contains("uniform vec2 glis_tile_origin;\n"));
+    assert_eq!(code.version(), GLSLVersion{number: 330, es: false});
+    for code in
+    [
+        "uniform sampler2D previous;",
+        "#define compute 8",
+        "#define vertices 3"
+    ]
+    {
+        AnnotatedGLSL::new(code).unwrap().into_tiled().map(|_| ()).unwrap_err();
+    }
+    let code = "#version 300 es\n\
+        #extension GL_OES_standard_derivatives : enable\n\
+        #ifdef GL_EXT_shader_texture_lod\n\
+        #extension GL_EXT_shader_texture_lod : enable\n\
+        #endif\n\
+        precision highp float;\n\
+        #extension GL_late : enable";
+    assert_eq!(declarations_index(code), 5);
+    assert_eq!(declarations_index("#version 330\nfloat x;"), 1);
+    assert_eq!(declarations_index("float x;"), 0);
+    let code = AnnotatedGLSL::new("#version 300 es\n#extension GL_OES_standard_derivatives : enable\nvoid main(){}")
+        .unwrap()
+        .into_tiled()
+        .unwrap();
+    let lines: Vec<&str> = code.code().lines().collect();
+    assert_eq!(lines[1], "#extension GL_OES_standard_derivatives : enable");
+    assert_eq!(lines[2], "#ifdef GL_ES");
+    assert_eq!(code.source_map().original(code.code().lines().count()), Some(3))
+}
@@ -0,0 +1,270 @@
+use std::time::{Duration, Instant};
+use ogl::*;
+
+// ------------------------------------------------------------
+
+// Length of the glyphs array in the text shader
+pub const MAX_GLYPHS: usize = 384;
+pub const HUD_GRID: [usize; 2] = [16, 8];
+pub const NOTIFICATION_GRID: [usize; 2] = [48, 8];
+
+// 3x5 bitmaps, rows top to bottom with the leftmost pixel in the highest bit
+fn glyph(character: char) -> GLint
+{
+    match character.to_ascii_uppercase()
+    {
+        '0' | 'O' => 0b111_101_101_101_111,
+        '1' => 0b010_110_010_010_111,
+        '2' => 0b111_001_111_100_111,
+        '3' => 0b111_001_111_001_111,
+        '4' => 0b101_101_111_001_001,
+        '5' | 'S' => 0b111_100_111_001_111,
+        '6' => 0b111_100_111_101_111,
+        '7' => 0b111_001_001_001_001,
+        '8' => 0b111_101_111_101_111,
+        '9' => 0b111_101_111_001_111,
+        'A' => 0b010_101_111_101_101,
+        'B' => 0b110_101_110_101_110,
+        'C' => 0b111_100_100_100_111,
+        'D' => 0b110_101_101_101_110,
+        'E' => 0b111_100_110_100_111,
+        'F' => 0b111_100_110_100_100,
+        'G' => 0b111_100_101_101_111,
+        'H' => 0b101_101_111_101_101,
+        'I' => 0b111_010_010_010_111,
+        'J' => 0b001_001_001_101_111,
+        'K' => 0b101_101_110_101_101,
+        'L' => 0b100_100_100_100_111,
+        'M' => 0b101_111_111_101_101,
+        'N' => 0b110_101_101_101_101,
+        'P' => 0b111_101_111_100_100,
+        'Q' => 0b111_101_101_111_001,
+        'R' => 0b110_101_110_101_101,
+        'T' => 0b111_010_010_010_010,
+        'U' => 0b101_101_101_101_111,
+        'V' => 0b101_101_101_101_010,
+        'W' => 0b101_101_111_111_101,
+        'X' => 0b101_101_010_101_101,
+        'Y' => 0b101_101_010_010_010,
+        'Z' => 0b111_001_010_100_111,
+        '.' => 0b000_000_000_000_010,
+        '/' => 0b001_001_010_100_100,
+        ':' => 0b000_010_000_010_000,
+        '-' => 0b000_000_111_000_000,
+        _ => 0
+    }
+}
+
+// Text laid out on a grid of columns and rows, longer lines are cut
+pub fn glyphs(lines: &[String], [columns, rows]: [usize; 2]) -> Vec<GLint>
+{
+    assert!(columns * rows <= MAX_GLYPHS);
+    let mut glyphs = vec![0; columns * rows];
+    for (row, line) in lines.iter().take(rows).enumerate()
+    {
+        for (column, character) in line.chars().take(columns).enumerate()
+        {
+            glyphs[row * columns + column] = glyph(character)
+        }
+    }
+    glyphs
+}
+
+// Messages broken into lines of at most `columns`
+// characters, keeping the last `rows` lines
+pub fn wrap(messages: &[String], [columns, rows]: [usize; 2]) -> Vec<String>
+{
+    let mut lines: Vec<String> = messages.iter()
+        .flat_map(|message| message.lines())
+        .flat_map
+        (
+            |line|
+            {
+                let characters: Vec<char> = line.trim_end().chars().collect();
+                match characters.is_empty()
+                {
+                    true => vec!(),
+                    false => characters.chunks(columns)
+                        .map(|chunk| chunk.iter().collect())
+                        .collect()
+                }
+            }
+        )
+        .collect();
+    let skipped = lines.len().saturating_sub(rows);
+    lines.drain(..skipped);
+    lines
+}
+
+// ------------------------------------------------------------
+
+// Results are read a few frames late so that
+// waiting on them never stalls the pipeline
+pub struct PassTimer
+{
+    queries: Vec<(Query, bool)>,
+    next: usize,
+    elapsed: Duration
+}
+
+impl PassTimer
+{
+    const LATENCY: usize = 3;
+
+    pub fn new(pointers: &FunctionPointers) -> Self
+    {
+        let queries = (0..Self::LATENCY)
+            .map(|_| (Query::new(pointers), false))
+            .collect();
+        Self{queries, next: 0, elapsed: Duration::ZERO}
+    }
+
+    pub fn measure<T>(&mut self, pass: impl FnOnce() -> T) -> T
+    {
+        let (query, issued) = &mut self.queries[self.next];
+        if *issued
+        {
+            match query.available()
+            {
+                true => self.elapsed = Duration::from_nanos(query.result()),
+                false => return pass()
+            }
+        }
+        query.begin(TIME_ELAPSED);
+        let result = pass();
+        query.end(TIME_ELAPSED);
+        *issued = true;
+        self.next = (self.next + 1) % Self::LATENCY;
+        result
+    }
+
+    pub fn elapsed(&self) -> Duration
+    {
+        self.elapsed
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+pub enum Pass
+{
+    Compute,
+    Render,
+    Blit
+}
+
+impl Pass
+{
+    const ALL: [Self; 3] = [Self::Compute, Self::Render, Self::Blit];
+
+    fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Self::Compute => "COMPUTE",
+            Self::Render => "RENDER",
+            Self::Blit => "BLIT"
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+pub struct FrameStats
+{
+    timers: [PassTimer; 3],
+    cpu: Duration,
+    interval: f32,
+    presented: Option<Instant>
+}
+
+impl FrameStats
+{
+    pub fn new(pointers: &FunctionPointers) -> Self
+    {
+        Self
+        {
+            timers: Pass::ALL.map(|_| PassTimer::new(pointers)),
+            cpu: Duration::ZERO,
+            interval: 0.0,
+            presented: None
+        }
+    }
+
+    pub fn timer(&mut self, pass: Pass) -> &mut PassTimer
+    {
+        &mut self.timers[pass as usize]
+    }
+
+    // The interval between frames is smoothed for a readable frame rate
+    pub fn present(&mut self, cpu: Duration) -> ()
+    {
+        self.cpu = cpu;
+        let now = Instant::now();
+        if let Some(presented) = self.presented
+        {
+            let interval = (now - presented).as_secs_f32();
+            self.interval = match self.interval == 0.0
+            {
+                true => interval,
+                false => self.interval * 0.9 + interval * 0.1
+            }
+        }
+        self.presented = Some(now)
+    }
+
+    pub fn lines(&self, target: u32, dropped: u32, compute: bool) -> Vec<String>
+    {
+        let milliseconds = |duration: Duration| duration.as_secs_f32() * 1000.0;
+        let passes: Vec<Pass> = Pass::ALL.into_iter()
+            .filter(|pass| compute || !matches!(pass, Pass::Compute))
+            .collect();
+        let gpu = passes.iter()
+            .map(|pass| self.timers[*pass as usize].elapsed())
+            .sum();
+        let mut lines = vec!(format!("{:<8}{:>5.2} MS", "GPU", milliseconds(gpu)));
+        for pass in passes
+        {
+            lines.push
+            (
+                format!
+                (
+                    " {:<7}{:>5.2} MS",
+                    pass.name(),
+                    milliseconds(self.timers[pass as usize].elapsed())
+                )
+            )
+        }
+        let fps = match self.interval > 0.0
+        {
+            true => 1.0 / self.interval,
+            false => 0.0
+        };
+        lines.push(format!("{:<8}{:>5.2} MS", "CPU", milliseconds(self.cpu)));
+        lines.push(format!("{:<8}{:>4.0}/{target}", "FPS", fps));
+        lines.push(format!("{:<8}{:>5}", "DROPPED", dropped));
+        lines
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn layout() -> ()
+{
+    let [columns, rows] = HUD_GRID;
+    let glyphs = glyphs(&[String::from("A1"), "x".repeat(20)], HUD_GRID);
+    assert_eq!(glyphs.len(), columns * rows);
+    assert_eq!(glyphs[0], 0b010_101_111_101_101);
+    assert_eq!(glyphs[1], 0b010_110_010_010_111);
+    assert_eq!(glyphs[2], 0);
+    assert!(glyphs[columns..2 * columns].iter().all(|glyph| *glyph != 0));
+    assert!(glyphs[2 * columns..].iter().all(|glyph| *glyph == 0));
+    let lines = wrap
+    (
+        &[String::from("abcdefg\n\nhi"), String::from("jk")],
+        [3, 3]
+    );
+    assert_eq!(lines, ["g", "hi", "jk"])
+}
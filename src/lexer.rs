@@ -0,0 +1,359 @@
+use std::{fmt, ops::Range};
+
+// ------------------------------------------------------------
+
+// Byte range in the source with the 1-based line and column it starts at
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Span
+{
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize
+}
+
+impl Span
+{
+    pub fn range(&self) -> Range<usize>
+    {
+        self.start..self.end
+    }
+}
+
+impl fmt::Display for Span
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(formatter, "line {}, column {}", self.line, self.column)
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Debug)]
+pub struct SyntaxError
+{
+    pub span: Span,
+    pub message: String
+}
+
+impl SyntaxError
+{
+    pub fn new(span: Span, message: impl Into<String>) -> Self
+    {
+        Self{span, message: message.into()}
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+impl fmt::Display for SyntaxError
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!(formatter, "{}: {}", self.span, self.message)
+    }
+}
+
+pub type SyntaxResult<T> = std::result::Result<T, SyntaxError>;
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenKind
+{
+    Identifier,
+    Number,
+    // Not GLSL, only used to quote annotation values
+    String,
+    Punctuation,
+    Comment,
+    // A whole preprocessor line, continuations included
+    Directive
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Token
+{
+    pub kind: TokenKind,
+    pub span: Span
+}
+
+impl Token
+{
+    pub fn text<'a>(&self, source: &'a str) -> &'a str
+    {
+        &source[self.span.range()]
+    }
+
+    pub fn is(&self, source: &str, text: &str) -> bool
+    {
+        self.kind != TokenKind::Comment && self.text(source) == text
+    }
+}
+
+// ------------------------------------------------------------
+
+struct Cursor<'a>
+{
+    source: &'a str,
+    offset: usize,
+    line: usize,
+    column: usize
+}
+
+impl<'a> Cursor<'a>
+{
+    fn peek(&self) -> Option<char>
+    {
+        self.source[self.offset..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char>
+    {
+        self.source[self.offset..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char>
+    {
+        let character = self.peek()?;
+        self.offset += character.len_utf8();
+        match character
+        {
+            '\n' =>
+            {
+                self.line += 1;
+                self.column = 1
+            }
+            _ => self.column += 1
+        }
+        Some(character)
+    }
+
+    fn bump_while(&mut self, condition: impl Fn(char) -> bool) -> ()
+    {
+        while self.peek().is_some_and(&condition)
+        {
+            self.bump();
+        }
+    }
+
+    fn span_from(&self, start: (usize, usize, usize)) -> Span
+    {
+        let (offset, line, column) = start;
+        Span{start: offset, end: self.offset, line, column}
+    }
+
+    fn position(&self) -> (usize, usize, usize)
+    {
+        (self.offset, self.line, self.column)
+    }
+
+    // Comments are kept as tokens, the caller decides whether they
+    // separate tokens or vanish from annotation values
+    fn comment(&mut self) -> bool
+    {
+        match (self.peek(), self.peek_second())
+        {
+            (Some('/'), Some('/')) =>
+            {
+                self.bump_while(|character| character != '\n');
+                true
+            }
+            (Some('/'), Some('*')) =>
+            {
+                self.bump();
+                self.bump();
+                while let Some(character) = self.bump()
+                {
+                    if character == '*' && self.peek() == Some('/')
+                    {
+                        self.bump();
+                        break
+                    }
+                }
+                true
+            }
+            _ => false
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+fn is_identifier_start(character: char) -> bool
+{
+    character.is_ascii_alphabetic() || character == '_'
+}
+
+fn is_identifier(character: char) -> bool
+{
+    character.is_ascii_alphanumeric() || character == '_'
+}
+
+// Whitespace is skipped. A `#` that only follows whitespace on its line starts a
+// directive running to the end of the line, joined with the next on a trailing `\`
+pub fn tokenize(source: &str) -> SyntaxResult<Vec<Token>>
+{
+    let mut cursor = Cursor{source, offset: 0, line: 1, column: 1};
+    let mut tokens = vec!();
+    let mut line_start = true;
+    while let Some(character) = cursor.peek()
+    {
+        let start = cursor.position();
+        if character == '\n'
+        {
+            cursor.bump();
+            line_start = true;
+            continue
+        }
+        if character.is_whitespace()
+        {
+            cursor.bump();
+            continue
+        }
+        let kind = if cursor.comment()
+        {
+            TokenKind::Comment
+        }
+        else if character == '#' && line_start
+        {
+            loop
+            {
+                if cursor.comment()
+                {
+                    continue
+                }
+                match cursor.peek()
+                {
+                    None | Some('\n') => break,
+                    Some('"') =>
+                    {
+                        cursor.bump();
+                        cursor.bump_while(|character| character != '"' && character != '\n');
+                        if cursor.peek() == Some('"')
+                        {
+                            cursor.bump();
+                        }
+                    }
+                    Some('\\') if matches!(cursor.peek_second(), Some('\n')) =>
+                    {
+                        cursor.bump();
+                        cursor.bump();
+                    }
+                    Some('\\') if source[cursor.offset..].starts_with("\\\r\n") =>
+                    {
+                        cursor.bump();
+                        cursor.bump();
+                        cursor.bump();
+                    }
+                    Some(_) =>
+                    {
+                        cursor.bump();
+                    }
+                }
+            }
+            TokenKind::Directive
+        }
+        else if is_identifier_start(character)
+        {
+            cursor.bump_while(is_identifier);
+            TokenKind::Identifier
+        }
+        else if character.is_ascii_digit()
+            || (character == '.' && cursor.peek_second().is_some_and(|next| next.is_ascii_digit()))
+        {
+            cursor.bump_while(|character| is_identifier(character) || character == '.');
+            TokenKind::Number
+        }
+        else if character == '"'
+        {
+            cursor.bump();
+            cursor.bump_while(|character| character != '"' && character != '\n');
+            if cursor.bump() != Some('"')
+            {
+                return Err(SyntaxError::new(cursor.span_from(start), "Unterminated string"))
+            }
+            TokenKind::String
+        }
+        else
+        {
+            cursor.bump();
+            TokenKind::Punctuation
+        };
+        let span = cursor.span_from(start);
+        // A directive's own newline is left for the next iteration
+        line_start = kind == TokenKind::Comment && line_start
+            && !source[span.range()].contains('\n');
+        tokens.push(Token{kind, span})
+    }
+    Ok(tokens)
+}
+
+// Tokens of a directive's body, after the `#`, with spans into the whole source
+pub fn tokenize_directive(source: &str, directive: &Token) -> SyntaxResult<Vec<Token>>
+{
+    let span = directive.span;
+    let body = &source[span.start + 1..span.end];
+    let tokens = tokenize(&body.replace("\\\r\n", "   ").replace("\\\n", "  "))?;
+    let offset = |token: Token|
+    {
+        let start = span.start + 1 + token.span.start;
+        let end = span.start + 1 + token.span.end;
+        let before = &source[span.start..start];
+        let lines = before.matches('\n').count();
+        let column = match before.rfind('\n')
+        {
+            Some(newline) => source[span.start + newline + 1..start].chars().count() + 1,
+            None => span.column + before.chars().count()
+        };
+        Token{kind: token.kind, span: Span{start, end, line: span.line + lines, column}}
+    };
+    Ok(tokens.into_iter().map(offset).collect())
+}
+
+// Source text between two tokens with comments removed, trimmed
+pub fn text_between(source: &str, tokens: &[Token]) -> String
+{
+    let (first, last) = match (tokens.first(), tokens.last())
+    {
+        (Some(first), Some(last)) => (first.span.start, last.span.end),
+        _ => return String::new()
+    };
+    let mut text = String::new();
+    let mut at = first;
+    for comment in tokens.iter().filter(|token| token.kind == TokenKind::Comment)
+    {
+        text += &source[at..comment.span.start];
+        at = comment.span.end
+    }
+    text += &source[at..last];
+    text.trim().to_string()
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn tokens() -> ()
+{
+    use TokenKind::*;
+    let source = "  #define a 1 \\\n  b // x\nuniform /* c */ sampler2D t @ \"a b.png\";\n# x";
+    let tokens = tokenize(source).unwrap();
+    let kinds: Vec<TokenKind> = tokens.iter().map(|token| token.kind).collect();
+    assert_eq!
+    (
+        kinds,
+        [Directive, Identifier, Comment, Identifier, Identifier, Punctuation, String, Punctuation, Directive]
+    );
+    assert_eq!(tokens[0].text(source), "#define a 1 \\\n  b // x");
+    assert_eq!((tokens[3].span.line, tokens[3].span.column), (3, 17));
+    assert_eq!(tokens[6].text(source), "\"a b.png\"");
+    let body = tokenize_directive(source, &tokens[0]).unwrap();
+    let texts: Vec<&str> = body.iter().map(|token| token.text(source)).collect();
+    assert_eq!(texts, ["define", "a", "1", "b", "// x"]);
+    assert_eq!((body[1].span.line, body[1].span.column), (1, 11));
+    assert_eq!((body[3].span.line, body[3].span.column), (2, 3));
+    assert_eq!(text_between("a /* b */c d", &tokenize("a /* b */c d").unwrap()), "a c d");
+    let error = tokenize("\n  \"open").unwrap_err();
+    assert_eq!((error.span.line, error.span.column), (2, 3))
+}
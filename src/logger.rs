@@ -0,0 +1,177 @@
+use std::
+{
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::{Mutex, PoisonError}
+};
+use super::timeline::*;
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level
+{
+    Debug,
+    Info,
+    Warning,
+    Error
+}
+
+impl Level
+{
+    fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warning => "WARNING",
+            Self::Error => "ERROR"
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self>
+    {
+        match name.to_ascii_lowercase().as_str()
+        {
+            "debug" => Some(Self::Debug),
+            "info" => Some(Self::Info),
+            "warning" | "warn" => Some(Self::Warning),
+            "error" => Some(Self::Error),
+            _ => None
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+struct Logger
+{
+    level: Level,
+    file: Option<File>,
+    notifications: Vec<String>
+}
+
+static LOGGER: Mutex<Logger> = Mutex::new
+(
+    Logger
+    {
+        level: Level::Info,
+        file: None,
+        notifications: Vec::new()
+    }
+);
+
+// Notifications nobody collects must not pile up
+const MAX_NOTIFICATIONS: usize = 16;
+
+// ------------------------------------------------------------
+
+// GLIS_LOG sets the minimum level (debug, info, warning or error) and
+// GLIS_LOG_FILE a file the log is appended to
+pub fn init_from_env() -> anyhow::Result<()>
+{
+    let mut logger = LOGGER.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Ok(name) = std::env::var("GLIS_LOG")
+    {
+        logger.level = Level::parse(&name).ok_or_else
+        (
+            || anyhow::anyhow!("Unknown log level `{name}` in GLIS_LOG")
+        )?
+    }
+    if let Some(path) = std::env::var_os("GLIS_LOG_FILE")
+    {
+        logger.file = Some
+        (
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?
+        )
+    }
+    Ok(())
+}
+
+fn timestamp(date: Date) -> String
+{
+    let seconds = date.seconds;
+    format!
+    (
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:06.3}",
+        date.year,
+        date.month,
+        date.day,
+        (seconds / 3600.0) as u32,
+        (seconds % 3600.0 / 60.0) as u32,
+        seconds % 60.0
+    )
+}
+
+pub fn log(level: Level, message: impl Display) -> ()
+{
+    let mut logger = LOGGER.lock().unwrap_or_else(PoisonError::into_inner);
+    if level < logger.level
+    {
+        return
+    }
+    let message = message.to_string();
+    let line = format!("{} {:<7} {message}", timestamp(Date::now()), level.name());
+    eprintln!("{line}");
+    if let Some(file) = &mut logger.file
+    {
+        let _ = writeln!(file, "{line}");
+    }
+    if level >= Level::Warning
+    {
+        if logger.notifications.len() == MAX_NOTIFICATIONS
+        {
+            logger.notifications.remove(0);
+        }
+        logger.notifications.push(message)
+    }
+}
+
+pub fn error(message: impl Display) -> ()
+{
+    log(Level::Error, message)
+}
+
+pub fn warning(message: impl Display) -> ()
+{
+    log(Level::Warning, message)
+}
+
+pub fn info(message: impl Display) -> ()
+{
+    log(Level::Info, message)
+}
+
+#[allow(unused)]
+pub fn debug(message: impl Display) -> ()
+{
+    log(Level::Debug, message)
+}
+
+// Warnings and errors logged since the last call,
+// the app shows them on the canvas without blocking
+pub fn take_notifications() -> Vec<String>
+{
+    let mut logger = LOGGER.lock().unwrap_or_else(PoisonError::into_inner);
+    std::mem::take(&mut logger.notifications)
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn format() -> ()
+{
+    assert_eq!
+    (
+        timestamp(Date::from_unix(951782400.0 + 13.0 * 3600.0 + 5.0 * 60.0 + 7.25)),
+        "2000-02-29 13:05:07.250"
+    );
+    assert_eq!(Level::parse("Warn"), Some(Level::Warning));
+    assert_eq!(Level::parse("verbose"), None);
+    assert!(Level::Debug < Level::Error)
+}
@@ -1,14 +1,26 @@
 
 mod abort;
+mod logger;
 mod watcher;
 mod window;
 mod shaders;
 mod timeline;
+mod color;
 mod canvas;
+mod lexer;
+mod preprocessor;
+mod sourcemap;
 mod glsl;
 mod picture;
+mod assets;
+mod paths;
+mod mesh;
+mod hud;
+mod overlay;
 mod runtime;
 mod interop;
+mod bench;
+mod poster;
 mod app;
 
 // ------------------------------------------------------------
@@ -20,8 +32,40 @@ use winit::{event::*, event_loop::*};
 
 fn main() -> !
 {
-    let path = std::env::args().nth(1).unwrap_or_default();
+    logger::init_from_env().aborts();
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_default();
     let event_loop = EventLoop::new();
+    // Shaders named like a subcommand still open, `--` forces a path
+    let (path, subcommand) = match path.as_str()
+    {
+        "--" => (args.next().unwrap_or_default(), false),
+        _ => (path.clone(), !std::path::Path::new(&path).exists())
+    };
+    let command = match path.as_str()
+    {
+        _ if !subcommand => None,
+        "bench" => Some
+        (
+            bench::BenchOptions::parse(args)
+                .and_then(|options| bench::run(&event_loop, options))
+        ),
+        "poster" => Some
+        (
+            poster::PosterOptions::parse(args)
+                .and_then(|options| poster::run(&event_loop, options))
+        ),
+        _ => None
+    };
+    if let Some(result) = command
+    {
+        if let Err(error) = result
+        {
+            logger::error(error);
+            std::process::exit(1)
+        }
+        std::process::exit(0)
+    }
     let mut app = app::App::new(&event_loop, path).aborts();
     event_loop.run
     (
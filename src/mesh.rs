@@ -0,0 +1,588 @@
+use std::{io, fmt, fs, path::*};
+
+// ------------------------------------------------------------
+
+#[derive(Debug)]
+pub enum MeshError
+{
+    IO(io::Error),
+    Parse(usize, String),
+    UnsupportedFormat(String),
+    Empty
+}
+
+impl std::error::Error for MeshError {}
+
+impl fmt::Display for MeshError
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::IO(error) => write!(formatter, "{error}"),
+            Self::Parse(line, description)
+                => write!(formatter, "Mesh parse error at line {line}: {description}"),
+            Self::UnsupportedFormat(format)
+                => write!(formatter, "Unsupported mesh format `{format}`"),
+            Self::Empty
+                => write!(formatter, "Mesh has no faces")
+        }
+    }
+}
+
+pub type MeshResult<T> = std::result::Result<T, MeshError>;
+
+// ------------------------------------------------------------
+
+struct Corner
+{
+    position: [f32; 3],
+    normal: Option<[f32; 3]>,
+    uv: [f32; 2]
+}
+
+fn face_normal(corners: &[Corner; 3]) -> [f32; 3]
+{
+    let [a, b, c] = [0, 1, 2].map(|i| corners[i].position);
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n =
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0]
+    ];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    match length > 0.0
+    {
+        true => n.map(|x| x / length),
+        false => [0.0, 0.0, 1.0]
+    }
+}
+
+// ------------------------------------------------------------
+
+// Unindexed triangle list, ready to be uploaded as vertex attributes
+#[derive(Default)]
+pub struct Mesh
+{
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub uvs: Vec<f32>
+}
+
+impl Mesh
+{
+    pub fn open(path: impl AsRef<Path>) -> MeshResult<Self>
+    {
+        let path = path.as_ref();
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match extension.as_str()
+        {
+            "obj" => fs::read_to_string(path).map_err(MeshError::IO)
+                .and_then(|source| Self::from_obj(&source)),
+            "ply" => fs::read(path).map_err(MeshError::IO)
+                .and_then(|source| Self::from_ply(&source)),
+            _ => Err(MeshError::UnsupportedFormat(extension))
+        }
+    }
+
+    pub fn vertex_count(&self) -> u32
+    {
+        (self.positions.len() / 3) as _
+    }
+
+    pub fn bounds(&self) -> [[f32; 3]; 2]
+    {
+        let mut bounds = [[f32::MAX; 3], [f32::MIN; 3]];
+        for position in self.positions.chunks_exact(3)
+        {
+            for axis in 0..3
+            {
+                bounds[0][axis] = bounds[0][axis].min(position[axis]);
+                bounds[1][axis] = bounds[1][axis].max(position[axis])
+            }
+        }
+        bounds
+    }
+
+    fn push_triangle(&mut self, corners: [Corner; 3]) -> ()
+    {
+        let flat = face_normal(&corners);
+        for corner in corners
+        {
+            self.positions.extend(corner.position);
+            self.normals.extend(corner.normal.unwrap_or(flat));
+            self.uvs.extend(corner.uv)
+        }
+    }
+
+    fn finish(self) -> MeshResult<Self>
+    {
+        match self.positions.is_empty()
+        {
+            true => Err(MeshError::Empty),
+            false => Ok(self)
+        }
+    }
+
+    pub fn from_obj(source: &str) -> MeshResult<Self>
+    {
+        let mut positions: Vec<[f32; 3]> = vec!();
+        let mut normals: Vec<[f32; 3]> = vec!();
+        let mut uvs: Vec<[f32; 2]> = vec!();
+        let mut mesh = Self::default();
+        for (number, line) in source.lines().enumerate()
+        {
+            let number = number + 1;
+            let error = |description: &str|
+                MeshError::Parse(number, description.to_string());
+            let mut tokens = line.split_ascii_whitespace();
+            let floats = |tokens: std::str::SplitAsciiWhitespace, count|
+            {
+                let values = tokens.take(count).map(str::parse::<f32>)
+                    .collect::<Result<Vec<f32>, _>>()
+                    .map_err(|parse| error(&parse.to_string()))?;
+                match values.len() == count
+                {
+                    true => Ok(values),
+                    false => Err(error(&format!("Expected {count} values")))
+                }
+            };
+            match tokens.next()
+            {
+                Some("v") =>
+                {
+                    let v = floats(tokens, 3)?;
+                    positions.push([v[0], v[1], v[2]])
+                }
+                Some("vn") =>
+                {
+                    let v = floats(tokens, 3)?;
+                    normals.push([v[0], v[1], v[2]])
+                }
+                Some("vt") =>
+                {
+                    let v = floats(tokens, 2)?;
+                    uvs.push([v[0], v[1]])
+                }
+                Some("f") =>
+                {
+                    let mut corners = vec!();
+                    for token in tokens
+                    {
+                        let mut indices = token.split('/');
+                        let mut resolve = |count: usize| -> MeshResult<Option<usize>>
+                        {
+                            match indices.next()
+                            {
+                                None | Some("") => Ok(None),
+                                Some(index) =>
+                                {
+                                    let index = index.parse::<isize>()
+                                        .map_err(|parse| error(&parse.to_string()))?;
+                                    let resolved = match index < 0
+                                    {
+                                        true => count as isize + index,
+                                        false => index - 1
+                                    };
+                                    match (0..count as isize).contains(&resolved)
+                                    {
+                                        true => Ok(Some(resolved as usize)),
+                                        false => Err(error(&format!("Index {index} out of range")))
+                                    }
+                                }
+                            }
+                        };
+                        let position = resolve(positions.len())?
+                            .ok_or_else(|| error("Face corner without a position"))?;
+                        let uv = resolve(uvs.len())?;
+                        let normal = resolve(normals.len())?;
+                        corners.push
+                        (
+                            (
+                                positions[position],
+                                normal.map(|index| normals[index]),
+                                uv.map(|index| uvs[index]).unwrap_or_default()
+                            )
+                        )
+                    }
+                    if corners.len() < 3
+                    {
+                        return Err(error("Face with fewer than 3 corners"))
+                    }
+                    for index in 1..corners.len() - 1
+                    {
+                        let corner = |(position, normal, uv)| Corner{position, normal, uv};
+                        mesh.push_triangle
+                        (
+                            [
+                                corner(corners[0]),
+                                corner(corners[index]),
+                                corner(corners[index + 1])
+                            ]
+                        )
+                    }
+                }
+                _ => {}
+            }
+        }
+        mesh.finish()
+    }
+
+    pub fn from_ply(source: &[u8]) -> MeshResult<Self>
+    {
+        let header = PlyHeader::parse(source)?;
+        let mut body = PlyReader
+        {
+            source: &source[header.length..],
+            format: header.format,
+            line: header.lines
+        };
+        let mut mesh = Self::default();
+        let mut vertices = vec!();
+        for element in &header.elements
+        {
+            for record in 0..element.count
+            {
+                body.next_record();
+                let mut values = vec!();
+                for property in &element.properties
+                {
+                    match property
+                    {
+                        PlyProperty::Scalar(name, kind) =>
+                            values.push((name.as_str(), vec![body.read(kind)?])),
+                        PlyProperty::List(name, count_kind, kind) =>
+                        {
+                            let count = body.read(count_kind)? as usize;
+                            let list = (0..count).map(|_| body.read(kind))
+                                .collect::<MeshResult<Vec<f64>>>()?;
+                            values.push((name.as_str(), list))
+                        }
+                    }
+                }
+                let line = body.line;
+                let get = |names: &[&str]| match values.iter().find(|(name, _)| names.contains(name))
+                {
+                    Some((name, value)) => match value.first()
+                    {
+                        Some(&value) => Ok(Some(value as f32)),
+                        None => Err(MeshError::Parse(line, format!("Property `{name}` is an empty list")))
+                    },
+                    None => Ok(None)
+                };
+                match element.name.as_str()
+                {
+                    "vertex" =>
+                    {
+                        let position = [get(&["x"])?, get(&["y"])?, get(&["z"])?];
+                        let normal = [get(&["nx"])?, get(&["ny"])?, get(&["nz"])?];
+                        let uv =
+                        [
+                            get(&["u", "s", "texture_u"])?,
+                            get(&["v", "t", "texture_v"])?
+                        ];
+                        vertices.push
+                        (
+                            (
+                                position.map(Option::unwrap_or_default),
+                                match normal
+                                {
+                                    [Some(x), Some(y), Some(z)] => Some([x, y, z]),
+                                    _ => None
+                                },
+                                uv.map(Option::unwrap_or_default)
+                            )
+                        )
+                    }
+                    "face" =>
+                    {
+                        let indices = values.iter()
+                            .find(|(name, _)| ["vertex_indices", "vertex_index"].contains(name))
+                            .map(|(_, indices)| indices.clone())
+                            .unwrap_or_default();
+                        if indices.len() < 3
+                        {
+                            return Err
+                            (
+                                MeshError::Parse
+                                (
+                                    line,
+                                    format!("Face {record} has {} vertex indices, expected at least 3", indices.len())
+                                )
+                            )
+                        }
+                        // Casting would saturate negative indices to the first vertex
+                        let corner = |index: f64| match vertices.get(index as usize)
+                        {
+                            Some(&(position, normal, uv)) if index >= 0.0 && index.fract() == 0.0 =>
+                                Ok(Corner{position, normal, uv}),
+                            _ => Err
+                            (
+                                MeshError::Parse(line, format!("Index {index} out of range"))
+                            )
+                        };
+                        for index in 1..indices.len().saturating_sub(1)
+                        {
+                            mesh.push_triangle
+                            (
+                                [
+                                    corner(indices[0])?,
+                                    corner(indices[index])?,
+                                    corner(indices[index + 1])?
+                                ]
+                            )
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        mesh.finish()
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq)]
+enum PlyFormat
+{
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian
+}
+
+enum PlyProperty
+{
+    Scalar(String, String),
+    List(String, String, String)
+}
+
+struct PlyElement
+{
+    name: String,
+    count: usize,
+    properties: Vec<PlyProperty>
+}
+
+struct PlyHeader
+{
+    format: PlyFormat,
+    elements: Vec<PlyElement>,
+    length: usize,
+    lines: usize
+}
+
+impl PlyHeader
+{
+    fn parse(source: &[u8]) -> MeshResult<Self>
+    {
+        let mut format = None;
+        let mut elements: Vec<PlyElement> = vec!();
+        let mut offset = 0;
+        for (index, line) in source.split(|byte| *byte == b'\n').enumerate()
+        {
+            let number = index + 1;
+            offset += line.len() + 1;
+            let error = |description: &str|
+                MeshError::Parse(number, description.to_string());
+            let line = std::str::from_utf8(line)
+                .map_err(|_| error("Header is not valid text"))?;
+            let tokens: Vec<&str> = line.split_ascii_whitespace().collect();
+            match tokens.as_slice()
+            {
+                ["ply"] if index == 0 => {}
+                _ if index == 0 => return Err(error("Missing `ply` magic number")),
+                ["format", kind, _] => format = Some
+                (
+                    match *kind
+                    {
+                        "ascii" => PlyFormat::Ascii,
+                        "binary_little_endian" => PlyFormat::BinaryLittleEndian,
+                        "binary_big_endian" => PlyFormat::BinaryBigEndian,
+                        _ => return Err(error(&format!("Unknown format `{kind}`")))
+                    }
+                ),
+                ["element", name, count] => elements.push
+                (
+                    PlyElement
+                    {
+                        name: name.to_string(),
+                        count: count.parse().map_err(|_| error("Invalid element count"))?,
+                        properties: vec!()
+                    }
+                ),
+                ["property", "list", count_kind, kind, name] => elements.last_mut()
+                    .ok_or_else(|| error("Property outside of an element"))?
+                    .properties.push
+                    (
+                        PlyProperty::List
+                            (name.to_string(), count_kind.to_string(), kind.to_string())
+                    ),
+                ["property", kind, name] => elements.last_mut()
+                    .ok_or_else(|| error("Property outside of an element"))?
+                    .properties.push
+                    (
+                        PlyProperty::Scalar(name.to_string(), kind.to_string())
+                    ),
+                ["end_header"] =>
+                {
+                    let this = Self
+                    {
+                        format: format.ok_or_else(|| error("Missing format"))?,
+                        elements,
+                        length: offset.min(source.len()),
+                        lines: number
+                    };
+                    return Ok(this)
+                }
+                _ => {}
+            }
+        }
+        Err(MeshError::Parse(0, String::from("Missing `end_header`")))
+    }
+}
+
+// ------------------------------------------------------------
+
+struct PlyReader<'source>
+{
+    source: &'source [u8],
+    format: PlyFormat,
+    line: usize
+}
+
+impl PlyReader<'_>
+{
+    fn next_record(&mut self) -> ()
+    {
+        if self.format == PlyFormat::Ascii
+        {
+            self.line += 1
+        }
+    }
+
+    fn read(&mut self, kind: &str) -> MeshResult<f64>
+    {
+        let error = |description: String| MeshError::Parse(self.line, description);
+        if self.format == PlyFormat::Ascii
+        {
+            let start = self.source.iter()
+                .position(|byte| !byte.is_ascii_whitespace())
+                .ok_or_else(|| error(String::from("Unexpected end of file")))?;
+            let source = &self.source[start..];
+            let end = source.iter()
+                .position(u8::is_ascii_whitespace)
+                .unwrap_or(source.len());
+            let token = std::str::from_utf8(&source[..end]).unwrap_or_default();
+            self.source = &source[end..];
+            return token.parse::<f64>()
+                .map_err(|parse| error(format!("`{token}`: {parse}")))
+        }
+        let size = match kind
+        {
+            "char" | "int8" | "uchar" | "uint8" => 1,
+            "short" | "int16" | "ushort" | "uint16" => 2,
+            "int" | "int32" | "uint" | "uint32" | "float" | "float32" => 4,
+            "double" | "float64" => 8,
+            _ => return Err(error(format!("Unknown property type `{kind}`")))
+        };
+        if self.source.len() < size
+        {
+            return Err(error(String::from("Unexpected end of file")))
+        }
+        let mut bytes = [0u8; 8];
+        bytes[..size].copy_from_slice(&self.source[..size]);
+        if self.format == PlyFormat::BinaryBigEndian
+        {
+            bytes[..size].reverse()
+        }
+        self.source = &self.source[size..];
+        let [a, b, c, d, ..] = bytes;
+        let value = match kind
+        {
+            "char" | "int8" => a as i8 as f64,
+            "uchar" | "uint8" => a as f64,
+            "short" | "int16" => i16::from_le_bytes([a, b]) as f64,
+            "ushort" | "uint16" => u16::from_le_bytes([a, b]) as f64,
+            "int" | "int32" => i32::from_le_bytes([a, b, c, d]) as f64,
+            "uint" | "uint32" => u32::from_le_bytes([a, b, c, d]) as f64,
+            "float" | "float32" => f32::from_le_bytes([a, b, c, d]) as f64,
+            _ => f64::from_le_bytes(bytes)
+        };
+        Ok(value)
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn obj() -> ()
+{
+    let mesh = Mesh::from_obj
+    (
+        "
+        # quad
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        vt 0 0
+        vt 1 1
+        vn 0 0 -1
+        f 1/1 2/2 3/1 4/2
+        f -4//1 -3//1 -2//1
+        "
+    ).unwrap();
+    assert_eq!(mesh.vertex_count(), 9);
+    assert_eq!(&mesh.positions[..9], [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0]);
+    assert_eq!(&mesh.normals[..3], [0.0, 0.0, 1.0]);
+    assert_eq!(&mesh.normals[18..21], [0.0, 0.0, -1.0]);
+    assert_eq!(&mesh.uvs[..6], [0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+    assert_eq!(mesh.bounds(), [[0.0; 3], [1.0, 1.0, 0.0]]);
+    Mesh::from_obj("v 0 0 0\nf 1 2 3").map(|_|()).unwrap_err();
+    Mesh::from_obj("v 0 0").map(|_|()).unwrap_err();
+    Mesh::from_obj("").map(|_|()).unwrap_err();
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn ply() -> ()
+{
+    let header = |format: &str| format!
+    (
+        "ply\nformat {format} 1.0\ncomment triangle\nelement vertex 3\n\
+        property float x\nproperty float y\nproperty float z\n\
+        element face 1\nproperty list uchar int vertex_indices\nend_header\n"
+    );
+    let ascii = header("ascii") + "0 0 0\n2 0 0\n0 2 0\n3 0 1 2\n";
+    let mut binary = header("binary_little_endian").into_bytes();
+    for value in [0.0f32, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0]
+    {
+        binary.extend(value.to_le_bytes())
+    }
+    binary.push(3);
+    for index in [0i32, 1, 2]
+    {
+        binary.extend(index.to_le_bytes())
+    }
+    for source in [ascii.into_bytes(), binary]
+    {
+        let mesh = Mesh::from_ply(&source).unwrap();
+        assert_eq!(mesh.vertex_count(), 3);
+        assert_eq!(mesh.positions[3], 2.0);
+        assert_eq!(&mesh.normals[..3], [0.0, 0.0, 1.0])
+    }
+    Mesh::from_ply(b"ply\nformat ascii 1.0\n").map(|_|()).unwrap_err();
+    for body in ["0 0 0\n2 0 0\n0 2 0\n0\n", "0 0 0\n2 0 0\n0 2 0\n2 0 1\n", "0 0 0\n2 0 0\n0 2 0\n3 0 -1 2\n", "0 0 0\n2 0 0\n0 2 0\n3 0 1 3\n"]
+    {
+        Mesh::from_ply((header("ascii") + body).as_bytes()).map(|_| ()).unwrap_err();
+    }
+    let header = "ply\nformat ascii 1.0\nelement vertex 1\nproperty list uchar float x\nend_header\n";
+    Mesh::from_ply((header.to_string() + "0\n").as_bytes()).map(|_| ()).unwrap_err();
+}
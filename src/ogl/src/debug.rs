@@ -0,0 +1,231 @@
+use std::{fmt, ffi::*};
+use super::{bindings::*, pointers::*};
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum DebugSeverity
+{
+    Notification,
+    Low,
+    Medium,
+    High
+}
+
+impl DebugSeverity
+{
+    fn from_enum(severity: GLenum) -> Self
+    {
+        match severity
+        {
+            DEBUG_SEVERITY_HIGH => Self::High,
+            DEBUG_SEVERITY_MEDIUM => Self::Medium,
+            DEBUG_SEVERITY_LOW => Self::Low,
+            _ => Self::Notification
+        }
+    }
+
+    fn to_enum(self) -> GLenum
+    {
+        match self
+        {
+            Self::High => DEBUG_SEVERITY_HIGH,
+            Self::Medium => DEBUG_SEVERITY_MEDIUM,
+            Self::Low => DEBUG_SEVERITY_LOW,
+            Self::Notification => DEBUG_SEVERITY_NOTIFICATION
+        }
+    }
+}
+
+impl fmt::Display for DebugSeverity
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        let name = match self
+        {
+            Self::Notification => "notification",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high"
+        };
+        write!(formatter, "{name}")
+    }
+}
+
+// ------------------------------------------------------------
+
+pub struct DebugMessage
+{
+    pub source: GLenum,
+    pub kind: GLenum,
+    pub id: GLuint,
+    pub severity: DebugSeverity,
+    pub message: String
+}
+
+impl DebugMessage
+{
+    pub fn source_name(&self) -> &'static str
+    {
+        match self.source
+        {
+            DEBUG_SOURCE_API => "api",
+            DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+            DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+            DEBUG_SOURCE_THIRD_PARTY => "third party",
+            DEBUG_SOURCE_APPLICATION => "application",
+            _ => "other"
+        }
+    }
+
+    pub fn kind_name(&self) -> &'static str
+    {
+        match self.kind
+        {
+            DEBUG_TYPE_ERROR => "error",
+            DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated behavior",
+            DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+            DEBUG_TYPE_PORTABILITY => "portability",
+            DEBUG_TYPE_PERFORMANCE => "performance",
+            DEBUG_TYPE_MARKER => "marker",
+            _ => "other"
+        }
+    }
+}
+
+impl fmt::Display for DebugMessage
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        write!
+        (
+            formatter,
+            "[{} {} {}] {}",
+            self.severity,
+            self.source_name(),
+            self.kind_name(),
+            self.message
+        )
+    }
+}
+
+// ------------------------------------------------------------
+
+type DebugCallback = Box<dyn Fn(DebugMessage)>;
+
+extern "system" fn receive
+(
+    source: GLenum,
+    kind: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    message: *const GLchar,
+    callback: *mut c_void
+) -> ()
+{
+    let message = unsafe
+    {
+        std::slice::from_raw_parts(message as *const u8, length.max(0) as _)
+    };
+    let message = DebugMessage
+    {
+        source,
+        kind,
+        id,
+        severity: DebugSeverity::from_enum(severity),
+        message: String::from_utf8_lossy(message).trim_end().to_string()
+    };
+    let callback = unsafe{&*(callback as *const DebugCallback)};
+    callback(message)
+}
+
+// Routes KHR_debug messages at or above the minimum severity to the callback
+// until dropped, if the context supports it. Messages are synchronous so that
+// they arrive on the thread and at the call that caused them. Contexts are not
+// created with the debug flag so drivers may report fewer messages
+pub struct DebugOutput
+{
+    pointers: FunctionPointers,
+    #[allow(unused)]
+    callback: Box<DebugCallback>
+}
+
+impl DebugOutput
+{
+    pub fn new
+    (
+        pointers: &FunctionPointers,
+        minimum: DebugSeverity,
+        callback: impl Fn(DebugMessage) + 'static
+    ) -> Option<Self>
+    {
+        if !pointers.supports_debug()
+        {
+            return None
+        }
+        let callback: Box<DebugCallback> = Box::new(Box::new(callback));
+        unsafe
+        {
+            pointers.Enable(DEBUG_OUTPUT);
+            pointers.Enable(DEBUG_OUTPUT_SYNCHRONOUS);
+            pointers.DebugMessageCallback
+            (
+                Some(receive),
+                &*callback as *const DebugCallback as _
+            );
+            for level in
+            [
+                DebugSeverity::Notification,
+                DebugSeverity::Low,
+                DebugSeverity::Medium,
+                DebugSeverity::High
+            ]
+            {
+                pointers.DebugMessageControl
+                (
+                    DONT_CARE,
+                    DONT_CARE,
+                    level.to_enum(),
+                    0,
+                    std::ptr::null(),
+                    match level >= minimum
+                    {
+                        true => TRUE,
+                        false => FALSE
+                    }
+                )
+            }
+        }
+        Some(Self{pointers: pointers.clone(), callback})
+    }
+
+    // Reports an application message, filtered like the driver's ones
+    pub fn insert(&self, severity: DebugSeverity, message: &str) -> ()
+    {
+        unsafe
+        {
+            self.pointers.DebugMessageInsert
+            (
+                DEBUG_SOURCE_APPLICATION,
+                DEBUG_TYPE_MARKER,
+                0,
+                severity.to_enum(),
+                message.len() as _,
+                message.as_ptr() as _
+            )
+        }
+    }
+}
+
+impl Drop for DebugOutput
+{
+    fn drop(&mut self) -> ()
+    {
+        unsafe
+        {
+            self.pointers.DebugMessageCallback(None, std::ptr::null());
+            self.pointers.Disable(DEBUG_OUTPUT)
+        }
+    }
+}
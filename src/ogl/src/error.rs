@@ -12,7 +12,12 @@ pub enum OGLError
     AttributeCreate(String),
     AttributeNotFound(String),
     UniformNotFound(String),
+    UniformTypeMismatch(String, GLenum, GLenum),
+    StaleUniformHandle(GLint),
+    BlockNotFound(String),
     FramebufferCreation(GLenum),
+    // Samplers needed and texture units available
+    TextureUnits(usize, GLint),
     GL(GLenum)
 }
 
@@ -39,6 +44,23 @@ impl fmt::Display for OGLError
                 "Uniform `{name}` does not correspond to an active uniform variable in program or
                 name is associated with a named uniform block"
             ),
+            Self::UniformTypeMismatch(name, declared, given) => write!
+            (
+                formatter,
+                "Uniform `{name}` is declared as `{}` but was given a `{}` value",
+                super::impls::type_name(*declared),
+                super::impls::type_name(*given)
+            ),
+            Self::StaleUniformHandle(location) => write!
+            (
+                formatter,
+                "Uniform handle at location {location} refers to a program that has since been relinked"
+            ),
+            Self::BlockNotFound(name) => write!
+            (
+                formatter,
+                "Block `{name}` is not an active uniform or shader storage block in program"
+            ),
             Self::FramebufferCreation(status) => write!
             (
                 formatter,
@@ -79,6 +101,11 @@ impl fmt::Display for OGLError
                     _ => "Unknown framebuffer error"
                 }
             ),
+            Self::TextureUnits(needed, available) => write!
+            (
+                formatter,
+                "{needed} samplers are needed but only {available} texture units are available"
+            ),
             Self::GL(flag) => write!
             (
                 formatter,
@@ -90,6 +117,7 @@ impl fmt::Display for OGLError
                     INVALID_OPERATION => "The specified operation is not allowed in the current state",
                     INVALID_FRAMEBUFFER_OPERATION => "The framebuffer object is not complete",
                     OUT_OF_MEMORY => "There is not enough memory left to execute the command",
+                    CONTEXT_LOST => "The OpenGL context has been lost due to a graphics card reset",
                     NO_ERROR => "Conflicting error reports",
                     _ => "Unknown OpenGL error"
                 }
@@ -98,6 +126,15 @@ impl fmt::Display for OGLError
     }
 }
 
+impl OGLError
+{
+    // Nothing created in a lost context can be used again
+    pub fn is_context_lost(&self) -> bool
+    {
+        matches!(self, Self::GL(CONTEXT_LOST))
+    }
+}
+
 // ------------------------------------------------------------
 
 pub type OGLResult<T, E = OGLError> = std::result::Result<T, E>;
@@ -1,27 +1,443 @@
 
-use std::ffi::*;
+use std::{ffi::*, marker::PhantomData, ops::{Deref, DerefMut}};
 use super::{bindings::*, pointers::*, raii, error::*, traits::*};
 
 // ------------------------------------------------------------
 
-impl raii::VertexBufferObject
+// Buffer operations act on the buffer bound to the target of its marker type
+impl<T: BufferTarget> raii::Buffer<T>
 {
-    pub fn fill<T>(&self, data: &[T]) -> ()
+    pub fn fill<D>(&self, data: &[D]) -> ()
     {
         let pointers = self.pointers();
         unsafe
         {
             pointers.BufferData
             (
-                ARRAY_BUFFER,
-                (data.len() * std::mem::size_of::<T>()) as _,
+                T::TARGET,
+                (data.len() * std::mem::size_of::<D>()) as _,
                 data.as_ptr() as _,
-                STATIC_DRAW
+                T::USAGE
+            )
+        }
+    }
+
+    // Zero-initialized
+    pub fn allocate(&self, size: usize) -> ()
+    {
+        self.fill(&vec![0u8; size])
+    }
+
+    pub fn size(&self) -> usize
+    {
+        let mut size = 0;
+        unsafe{self.pointers().GetBufferParameteri64v(T::TARGET, BUFFER_SIZE, &mut size)}
+        size as _
+    }
+
+    pub fn sub_data<D>(&self, offset: usize, data: &[D]) -> OGLResult<()>
+    {
+        let pointers = self.pointers();
+        pointers.clear_errors();
+        unsafe
+        {
+            pointers.BufferSubData
+            (
+                T::TARGET,
+                offset as _,
+                (data.len() * std::mem::size_of::<D>()) as _,
+                data.as_ptr() as _
+            )
+        }
+        pointers.get_error()
+    }
+
+    pub fn get_sub_data<D: Zero + Clone>(&self, offset: usize, count: usize)
+        -> OGLResult<Vec<D>>
+    {
+        let pointers = self.pointers();
+        let mut data = vec![D::ZERO; count];
+        pointers.clear_errors();
+        unsafe
+        {
+            pointers.GetBufferSubData
+            (
+                T::TARGET,
+                offset as _,
+                (count * std::mem::size_of::<D>()) as _,
+                data.as_mut_ptr() as _
+            )
+        }
+        pointers.get_error().map(|_| data)
+    }
+
+    // The whole buffer is viewed as a slice of D
+    pub fn map<D>(&self, access: MapAccess) -> OGLResult<BufferMapping<'_, T, D>>
+    {
+        let pointers = self.pointers();
+        let size = self.size();
+        pointers.clear_errors();
+        let data = unsafe
+        {
+            pointers.MapBufferRange
+            (
+                T::TARGET,
+                0,
+                size as _,
+                match access
+                {
+                    MapAccess::Read => MAP_READ_BIT,
+                    MapAccess::Write => MAP_WRITE_BIT,
+                    MapAccess::ReadWrite => MAP_READ_BIT | MAP_WRITE_BIT
+                }
+            )
+        };
+        pointers.get_error()?;
+        match data.is_null()
+        {
+            true => Err(OGLError::GL(INVALID_OPERATION)),
+            false => Ok
+            (
+                BufferMapping
+                {
+                    buffer: self,
+                    data: data as _,
+                    len: size / std::mem::size_of::<D>().max(1)
+                }
             )
         }
     }
 }
 
+impl<T: IndexedBufferTarget> raii::Buffer<T>
+{
+    pub fn bind_base(&self, index: GLuint) -> ()
+    {
+        unsafe{self.pointers().BindBufferBase(T::TARGET, index, **self)}
+    }
+
+    pub fn bind_range(&self, index: GLuint, offset: usize, size: usize) -> ()
+    {
+        unsafe
+        {
+            self.pointers().BindBufferRange
+                (T::TARGET, index, **self, offset as _, size as _)
+        }
+    }
+}
+
+impl raii::Buffer<PixelPackTarget>
+{
+    // Queues the copy into the buffer without waiting for it to complete,
+    // the buffer is grown when needed and unbound afterwards
+    pub fn read_framebuffer<D: TextureComponentDataType>
+    (
+        &self,
+        origin: [i32; 2],
+        resolution: [u32; 2],
+        channels: ChannelCount
+    ) -> OGLResult<()>
+    {
+        let pointers = self.pointers();
+        let size = (resolution[0] * resolution[1] * u8::from(channels) as u32) as usize
+            * std::mem::size_of::<D>();
+        self.bind();
+        if self.size() < size
+        {
+            self.allocate(size)
+        }
+        pointers.clear_errors();
+        unsafe
+        {
+            pointers.ReadPixels
+            (
+                origin[0], origin[1],
+                resolution[0] as _, resolution[1] as _,
+                channels.into(),
+                D::TYPE_ENUM,
+                0 as _
+            );
+            pointers.BindBuffer(PIXEL_PACK_BUFFER, 0)
+        }
+        pointers.get_error()
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+pub enum MapAccess
+{
+    Read,
+    Write,
+    ReadWrite
+}
+
+// ------------------------------------------------------------
+
+// Unmaps the buffer when dropped
+pub struct BufferMapping<'buffer, T: BufferTarget, D>
+{
+    buffer: &'buffer raii::Buffer<T>,
+    data: *mut D,
+    len: usize
+}
+
+impl<T: BufferTarget, D> Deref for BufferMapping<'_, T, D>
+{
+    type Target = [D];
+    fn deref(&self) -> &Self::Target
+    {
+        unsafe{std::slice::from_raw_parts(self.data, self.len)}
+    }
+}
+
+impl<T: BufferTarget, D> DerefMut for BufferMapping<'_, T, D>
+{
+    fn deref_mut(&mut self) -> &mut Self::Target
+    {
+        unsafe{std::slice::from_raw_parts_mut(self.data, self.len)}
+    }
+}
+
+impl<T: BufferTarget, D> Drop for BufferMapping<'_, T, D>
+{
+    fn drop(&mut self) -> ()
+    {
+        self.buffer.bind();
+        unsafe{self.buffer.pointers().UnmapBuffer(T::TARGET);}
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BlockLayout
+{
+    Std140,
+    Std430
+}
+
+// Packs block members following the layout rules, in declaration order
+pub struct BlockWriter
+{
+    layout: BlockLayout,
+    bytes: Vec<u8>,
+    alignment: usize
+}
+
+impl BlockWriter
+{
+    pub fn new(layout: BlockLayout) -> Self
+    {
+        Self{layout, bytes: vec!(), alignment: 1}
+    }
+
+    fn align(&mut self, alignment: usize) -> ()
+    {
+        let padded = (self.bytes.len() + alignment - 1) / alignment * alignment;
+        self.bytes.resize(padded, 0);
+        self.alignment = self.alignment.max(alignment)
+    }
+
+    pub fn push<M: BlockMember>(&mut self, member: M) -> &mut Self
+    {
+        self.align(M::ALIGNMENT);
+        member.write(&mut self.bytes);
+        self
+    }
+
+    // std140 rounds the array alignment and stride up to a vec4
+    pub fn push_array<M: BlockMember + Copy>(&mut self, members: &[M]) -> &mut Self
+    {
+        let alignment = match self.layout
+        {
+            BlockLayout::Std140 => (M::ALIGNMENT + 15) / 16 * 16,
+            BlockLayout::Std430 => M::ALIGNMENT
+        };
+        for member in members
+        {
+            self.align(alignment);
+            member.write(&mut self.bytes);
+        }
+        self.align(alignment);
+        self
+    }
+
+    // Members packed so far, without the padding that ends the block
+    pub fn bytes(&self) -> &[u8]
+    {
+        &self.bytes
+    }
+
+    // The block padded to its base alignment, the largest member alignment
+    // which std140 rounds up to a vec4
+    pub fn finish(mut self) -> Vec<u8>
+    {
+        let alignment = match self.layout
+        {
+            BlockLayout::Std140 => (self.alignment + 15) / 16 * 16,
+            BlockLayout::Std430 => self.alignment
+        };
+        self.align(alignment);
+        self.bytes
+    }
+}
+
+// ------------------------------------------------------------
+
+impl raii::FenceSync
+{
+    // Whether the fence got signaled within the timeout, flushing pending
+    // commands so that it eventually does
+    pub fn client_wait(&self, timeout: std::time::Duration) -> OGLResult<bool>
+    {
+        let pointers = self.pointers();
+        match unsafe
+        {
+            pointers.ClientWaitSync
+                (**self, SYNC_FLUSH_COMMANDS_BIT, timeout.as_nanos() as _)
+        }
+        {
+            ALREADY_SIGNALED | CONDITION_SATISFIED => Ok(true),
+            TIMEOUT_EXPIRED => Ok(false),
+            _ => Err(pointers.get_error().err().unwrap_or(OGLError::GL(INVALID_OPERATION)))
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+// Results are in nanoseconds, either elapsed between begin and end for
+// TIME_ELAPSED or the GPU clock once previous commands completed for TIMESTAMP
+impl raii::Query
+{
+    // Only one query per target can be active at a time
+    pub fn begin(&self, target: GLenum) -> ()
+    {
+        unsafe{self.pointers().BeginQuery(target, **self)}
+    }
+
+    pub fn end(&self, target: GLenum) -> ()
+    {
+        unsafe{self.pointers().EndQuery(target)}
+    }
+
+    pub fn timestamp(&self) -> ()
+    {
+        unsafe{self.pointers().QueryCounter(**self, TIMESTAMP)}
+    }
+
+    pub fn available(&self) -> bool
+    {
+        let mut available = 0;
+        unsafe
+        {
+            self.pointers().GetQueryObjectiv
+                (**self, QUERY_RESULT_AVAILABLE, &mut available)
+        }
+        available as GLboolean == TRUE
+    }
+
+    // Blocks until the result is available
+    pub fn result(&self) -> u64
+    {
+        let mut result = 0;
+        unsafe{self.pointers().GetQueryObjectui64v(**self, QUERY_RESULT, &mut result)}
+        result
+    }
+}
+
+// ------------------------------------------------------------
+
+struct Readback
+{
+    buffer: raii::PixelPackBuffer,
+    fence: Option<raii::FenceSync>,
+    len: usize
+}
+
+// Framebuffer reads cycle through pixel pack buffers, each request returns
+// the pixels requested as many calls ago as there are buffers in the ring
+pub struct ReadbackRing<D>
+{
+    readbacks: Vec<Readback>,
+    next: usize,
+    data: PhantomData<D>
+}
+
+impl<D> ReadbackRing<D>
+where D: TextureComponentDataType + Zero + Clone
+{
+    pub fn new(pointers: &FunctionPointers, depth: usize) -> Self
+    {
+        let readbacks = (0..depth.max(1)).map
+        (
+            |_| Readback
+            {
+                buffer: raii::PixelPackBuffer::new(pointers),
+                fence: None,
+                len: 0
+            }
+        ).collect();
+        Self{readbacks, next: 0, data: PhantomData}
+    }
+
+    pub fn depth(&self) -> usize
+    {
+        self.readbacks.len()
+    }
+
+    // Blocks until the oldest pending read completes
+    fn receive(&mut self, index: usize) -> OGLResult<Option<Vec<D>>>
+    {
+        let readback = &mut self.readbacks[index];
+        let fence = match readback.fence.take()
+        {
+            Some(fence) => fence,
+            None => return Ok(None)
+        };
+        while !fence.client_wait(std::time::Duration::from_millis(100))? {}
+        readback.buffer.bind();
+        let data = readback.buffer.get_sub_data(0, readback.len)?;
+        unsafe{fence.pointers().BindBuffer(PIXEL_PACK_BUFFER, 0)}
+        Ok(Some(data))
+    }
+
+    pub fn request
+    (
+        &mut self,
+        origin: [i32; 2],
+        resolution: [u32; 2],
+        channels: ChannelCount
+    ) -> OGLResult<Option<Vec<D>>>
+    {
+        let index = self.next;
+        let data = self.receive(index)?;
+        let readback = &mut self.readbacks[index];
+        readback.buffer.read_framebuffer::<D>(origin, resolution, channels)?;
+        readback.fence = Some(raii::FenceSync::new(readback.buffer.pointers()));
+        readback.len = (resolution[0] * resolution[1] * u8::from(channels) as u32) as _;
+        self.next = (index + 1) % self.readbacks.len();
+        Ok(data)
+    }
+
+    // Remaining reads, oldest first
+    pub fn flush(&mut self) -> OGLResult<Vec<Vec<D>>>
+    {
+        let mut frames = vec!();
+        for offset in 0..self.readbacks.len()
+        {
+            let index = (self.next + offset) % self.readbacks.len();
+            if let Some(data) = self.receive(index)?
+            {
+                frames.push(data)
+            }
+        }
+        Ok(frames)
+    }
+}
+
 // ------------------------------------------------------------
 
 impl raii::VertexArrayObject
@@ -125,6 +541,7 @@ impl raii::Shader
 
 // ------------------------------------------------------------
 
+#[derive(Clone, Copy)]
 pub enum LocationOf
 {
     Attribute,
@@ -227,6 +644,248 @@ impl raii::Program
 
 // ------------------------------------------------------------
 
+#[derive(Clone, Debug)]
+pub struct ActiveVariable
+{
+    pub name: String,
+    pub kind: GLenum,
+    pub size: GLint,
+    pub location: GLint
+}
+
+impl ActiveVariable
+{
+    pub fn type_name(&self) -> &'static str
+    {
+        type_name(self.kind)
+    }
+}
+
+pub fn type_name(kind: GLenum) -> &'static str
+{
+    match kind
+    {
+        FLOAT => "float",
+        FLOAT_VEC2 => "vec2",
+        FLOAT_VEC3 => "vec3",
+        FLOAT_VEC4 => "vec4",
+        DOUBLE => "double",
+        INT => "int",
+        INT_VEC2 => "ivec2",
+        INT_VEC3 => "ivec3",
+        INT_VEC4 => "ivec4",
+        UNSIGNED_INT => "uint",
+        UNSIGNED_INT_VEC2 => "uvec2",
+        UNSIGNED_INT_VEC3 => "uvec3",
+        UNSIGNED_INT_VEC4 => "uvec4",
+        BOOL => "bool",
+        BOOL_VEC2 => "bvec2",
+        BOOL_VEC3 => "bvec3",
+        BOOL_VEC4 => "bvec4",
+        FLOAT_MAT2 => "mat2",
+        FLOAT_MAT3 => "mat3",
+        FLOAT_MAT4 => "mat4",
+        SAMPLER_1D => "sampler1D",
+        SAMPLER_2D => "sampler2D",
+        SAMPLER_3D => "sampler3D",
+        SAMPLER_CUBE => "samplerCube",
+        SAMPLER_2D_ARRAY => "sampler2DArray",
+        INT_SAMPLER_2D => "isampler2D",
+        UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+        _ => "unknown type"
+    }
+}
+
+pub fn is_sampler(kind: GLenum) -> bool
+{
+    matches!
+    (
+        kind,
+        SAMPLER_1D | SAMPLER_2D | SAMPLER_3D | SAMPLER_CUBE
+            | SAMPLER_2D_ARRAY | SAMPLER_2D_SHADOW | SAMPLER_2D_MULTISAMPLE
+            | INT_SAMPLER_2D | UNSIGNED_INT_SAMPLER_2D
+            | IMAGE_2D | INT_IMAGE_2D | UNSIGNED_INT_IMAGE_2D
+    )
+}
+
+impl raii::Program
+{
+    fn active_variables(&self, of: LocationOf) -> Vec<ActiveVariable>
+    {
+        let pointers = self.pointers();
+        let (count_name, max_length_name) = match of
+        {
+            LocationOf::Attribute => (ACTIVE_ATTRIBUTES, ACTIVE_ATTRIBUTE_MAX_LENGTH),
+            LocationOf::Uniform => (ACTIVE_UNIFORMS, ACTIVE_UNIFORM_MAX_LENGTH)
+        };
+        let (mut count, mut max_length) = (0, 0);
+        unsafe
+        {
+            pointers.GetProgramiv(**self, count_name, &mut count);
+            pointers.GetProgramiv(**self, max_length_name, &mut max_length)
+        }
+        let mut variables = vec!();
+        for index in 0..count.max(0) as GLuint
+        {
+            let mut name = vec![0u8; max_length.max(1) as usize];
+            let (mut length, mut size, mut kind) = (0, 0, 0);
+            unsafe
+            {
+                match of
+                {
+                    LocationOf::Attribute => pointers.GetActiveAttrib
+                    (
+                        **self, index, max_length,
+                        &mut length, &mut size, &mut kind,
+                        name.as_mut_ptr() as _
+                    ),
+                    LocationOf::Uniform => pointers.GetActiveUniform
+                    (
+                        **self, index, max_length,
+                        &mut length, &mut size, &mut kind,
+                        name.as_mut_ptr() as _
+                    )
+                }
+            }
+            name.truncate(length as usize);
+            let mut name = String::from_utf8_lossy(&name).into_owned();
+            if name.ends_with("[0]")
+            {
+                name.truncate(name.len() - 3)
+            }
+            // Built-ins and uniform block members have no location
+            if let Ok(location) = self.location(&name, of)
+            {
+                variables.push(ActiveVariable{name, kind, size, location})
+            }
+        }
+        variables
+    }
+
+    pub fn active_uniforms(&self) -> Vec<ActiveVariable>
+    {
+        self.active_variables(LocationOf::Uniform)
+    }
+
+    pub fn active_attributes(&self) -> Vec<ActiveVariable>
+    {
+        self.active_variables(LocationOf::Attribute)
+    }
+}
+
+// ------------------------------------------------------------
+
+pub struct UniformHandle<T>
+{
+    generation: u64,
+    location: GLint,
+    data: PhantomData<T>
+}
+
+impl<T> Clone for UniformHandle<T>
+{
+    fn clone(&self) -> Self
+    {
+        Self
+        {
+            generation: self.generation,
+            location: self.location,
+            data: PhantomData
+        }
+    }
+}
+
+impl<T: ToUniform> UniformHandle<T>
+{
+    pub fn location(&self) -> GLint
+    {
+        self.location
+    }
+
+    // The program has to be in use
+    pub fn set(&self, program: &raii::Program, data: T) -> OGLResult<()>
+    {
+        if program.generation() != self.generation
+        {
+            return Err(OGLError::StaleUniformHandle(self.location))
+        }
+        data.to_uniform(program.pointers(), self.location);
+        Ok(())
+    }
+}
+
+impl raii::Program
+{
+    pub fn uniform<T: ToUniform>(&self, name: &str) -> OGLResult<UniformHandle<T>>
+    {
+        let uniform = self.active_uniforms().into_iter()
+            .find(|uniform| uniform.name == name)
+            .ok_or_else(|| OGLError::UniformNotFound(name.to_string()))?;
+        self.handle(&uniform)
+    }
+
+    pub fn handle<T: ToUniform>(&self, uniform: &ActiveVariable)
+        -> OGLResult<UniformHandle<T>>
+    {
+        match T::accepts(uniform.kind)
+        {
+            true => Ok
+            (
+                UniformHandle
+                {
+                    generation: self.generation(),
+                    location: uniform.location,
+                    data: PhantomData
+                }
+            ),
+            false => Err
+            (
+                OGLError::UniformTypeMismatch
+                    (uniform.name.clone(), uniform.kind, T::UNIFORM_TYPE)
+            )
+        }
+    }
+
+    pub fn uniform_block_binding(&self, name: &str, binding: GLuint) -> OGLResult<()>
+    {
+        let pointers = self.pointers();
+        let cname = CString::new(name).unwrap();
+        unsafe
+        {
+            match pointers.GetUniformBlockIndex(**self, cname.as_ptr())
+            {
+                INVALID_INDEX => Err(OGLError::BlockNotFound(name.to_string())),
+                index =>
+                {
+                    pointers.UniformBlockBinding(**self, index, binding);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    pub fn storage_block_binding(&self, name: &str, binding: GLuint) -> OGLResult<()>
+    {
+        let pointers = self.pointers();
+        let cname = CString::new(name).unwrap();
+        unsafe
+        {
+            match pointers.GetProgramResourceIndex
+                (**self, SHADER_STORAGE_BLOCK, cname.as_ptr())
+            {
+                INVALID_INDEX => Err(OGLError::BlockNotFound(name.to_string())),
+                index =>
+                {
+                    pointers.ShaderStorageBlockBinding(**self, index, binding);
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
 #[derive(Clone, Copy)]
 pub enum ChannelCount
 {
@@ -295,6 +954,30 @@ pub enum InterpolationType
 
 // ------------------------------------------------------------
 
+#[derive(Clone, Copy)]
+pub enum ImageAccess
+{
+    ReadOnly,
+    WriteOnly,
+    ReadWrite
+}
+
+// ------------------------------------------------------------
+
+// RGBA internal format storing components of the given type
+pub fn color_format<D: TextureComponentDataType>() -> GLenum
+{
+    match D::TYPE_ENUM
+    {
+        UNSIGNED_BYTE | BYTE => RGBA8,
+        UNSIGNED_SHORT | SHORT => RGBA16,
+        UNSIGNED_INT | INT | FLOAT => RGBA32F,
+        _ => unreachable!()
+    }
+}
+
+// ------------------------------------------------------------
+
 impl raii::Texture
 {
     pub fn setup
@@ -354,12 +1037,52 @@ impl raii::Texture
         }
     }
 
+    pub fn bind_image
+    (
+        &self,
+        unit: GLuint,
+        access: ImageAccess,
+        internal_format: GLenum
+    ) -> ()
+    {
+        unsafe
+        {
+            self.pointers().BindImageTexture
+            (
+                unit,
+                **self,
+                0,
+                FALSE,
+                0,
+                match access
+                {
+                    ImageAccess::ReadOnly => READ_ONLY,
+                    ImageAccess::WriteOnly => WRITE_ONLY,
+                    ImageAccess::ReadWrite => READ_WRITE
+                },
+                internal_format
+            )
+        }
+    }
+
     pub fn fill<D: TextureComponentDataType>
     (
         &self,
         image: Image<D>,
         mipmap: bool // **
     ) -> ()
+    {
+        self.fill_as(image, color_format::<D>(), mipmap)
+    }
+
+    // Like fill, with an explicit internal format such as SRGB8_ALPHA8
+    pub fn fill_as<D: TextureComponentDataType>
+    (
+        &self,
+        image: Image<D>,
+        internal_format: GLenum,
+        mipmap: bool
+    ) -> ()
     {
         let pointers = self.pointers();
         unsafe
@@ -368,13 +1091,7 @@ impl raii::Texture
             (
                 TEXTURE_2D,
                 0,
-                match D::TYPE_ENUM
-                {
-                    UNSIGNED_BYTE | BYTE => RGBA8,
-                    UNSIGNED_SHORT | SHORT => RGBA16,
-                    UNSIGNED_INT | INT | FLOAT => RGBA32F,
-                    _ => unreachable!()
-                } as _,
+                internal_format as _,
                 image.resolution[0] as _,
                 image.resolution[1] as _,
                 0,
@@ -396,6 +1113,54 @@ impl raii::Texture
 
 // ------------------------------------------------------------
 
+impl raii::Renderbuffer
+{
+    pub fn storage
+    (
+        &self,
+        internal_format: GLenum,
+        resolution: [u32; 2]
+    ) -> ()
+    {
+        unsafe
+        {
+            self.pointers().RenderbufferStorage
+            (
+                RENDERBUFFER,
+                internal_format,
+                resolution[0] as _,
+                resolution[1] as _
+            )
+        }
+    }
+
+    pub fn storage_multisample
+    (
+        &self,
+        samples: u32,
+        internal_format: GLenum,
+        resolution: [u32; 2]
+    ) -> OGLResult<()>
+    {
+        let pointers = self.pointers();
+        pointers.clear_errors();
+        unsafe
+        {
+            pointers.RenderbufferStorageMultisample
+            (
+                RENDERBUFFER,
+                samples as _,
+                internal_format,
+                resolution[0] as _,
+                resolution[1] as _
+            )
+        }
+        pointers.get_error()
+    }
+}
+
+// ------------------------------------------------------------
+
 impl raii::FramebufferObject
 {
     pub fn attach_color(&self, texture: &raii::Texture) -> OGLResult<()>
@@ -413,6 +1178,67 @@ impl raii::FramebufferObject
                 0
             )
         }
+        self.status()
+    }
+
+    pub fn attach_color_renderbuffer(&self, renderbuffer: &raii::Renderbuffer) -> OGLResult<()>
+    {
+        let pointers = self.pointers();
+        pointers.clear_errors();
+        unsafe
+        {
+            pointers.FramebufferRenderbuffer
+            (
+                FRAMEBUFFER,
+                COLOR_ATTACHMENT0,
+                RENDERBUFFER,
+                **renderbuffer
+            )
+        }
+        self.status()
+    }
+
+    // Copies the color of the whole framebuffer, resolving multisampling,
+    // the target is left bound
+    pub fn blit_color(&self, target: &raii::FramebufferObject, resolution: [u32; 2]) -> ()
+    {
+        let [width, height] = resolution.map(|size| size as GLint);
+        unsafe
+        {
+            let pointers = self.pointers();
+            pointers.BindFramebuffer(READ_FRAMEBUFFER, **self);
+            pointers.BindFramebuffer(DRAW_FRAMEBUFFER, **target);
+            pointers.BlitFramebuffer
+            (
+                0, 0, width, height,
+                0, 0, width, height,
+                COLOR_BUFFER_BIT,
+                NEAREST
+            );
+            pointers.BindFramebuffer(FRAMEBUFFER, **target)
+        }
+    }
+
+    pub fn attach_depth(&self, renderbuffer: &raii::Renderbuffer) -> OGLResult<()>
+    {
+        let pointers = self.pointers();
+        pointers.clear_errors();
+        unsafe
+        {
+            pointers.FramebufferRenderbuffer
+            (
+                FRAMEBUFFER,
+                DEPTH_ATTACHMENT,
+                RENDERBUFFER,
+                **renderbuffer
+            )
+        }
+        self.status()
+    }
+
+    fn status(&self) -> OGLResult<()>
+    {
+        let pointers = self.pointers();
         match unsafe{pointers.CheckFramebufferStatus(FRAMEBUFFER)}
         {
             FRAMEBUFFER_COMPLETE => Ok(()),
@@ -3,7 +3,14 @@ mod bindings; pub use bindings::
 {
     VERTEX_SHADER,
     FRAGMENT_SHADER,
+    COMPUTE_SHADER,
+    POINTS,
+    LINES,
+    LINE_STRIP,
+    LINE_LOOP,
     TRIANGLES,
+    TRIANGLE_STRIP,
+    TRIANGLE_FAN,
     GLenum,
     GLubyte,
     GLbyte,
@@ -14,16 +21,45 @@ mod bindings; pub use bindings::
     GLfloat,
     GLdouble,
     GLbitfield,
+    GLsizei,
+    FLOAT,
     COLOR_BUFFER_BIT,
     DEPTH_BUFFER_BIT,
     UNPACK_ALIGNMENT,
     PACK_ALIGNMENT,
     BLEND,
-    DEPTH_TEST
+    DEPTH_TEST,
+    DEPTH_COMPONENT24,
+    PROGRAM_POINT_SIZE,
+    ONE,
+    SRC_ALPHA,
+    ONE_MINUS_SRC_ALPHA,
+    RGBA32F,
+    SRGB8_ALPHA8,
+    TEXTURE_FETCH_BARRIER_BIT,
+    SHADER_IMAGE_ACCESS_BARRIER_BIT,
+    SHADER_STORAGE_BARRIER_BIT,
+    MAX_TEXTURE_SIZE,
+    MAX_RENDERBUFFER_SIZE,
+    MAX_TEXTURE_IMAGE_UNITS,
+    TIME_ELAPSED,
+    TIMESTAMP,
+    RENDERER,
+    VENDOR,
+    VERSION,
+    SHADING_LANGUAGE_VERSION,
+    MAJOR_VERSION,
+    MINOR_VERSION,
+    CONTEXT_PROFILE_MASK,
+    CONTEXT_COMPATIBILITY_PROFILE_BIT,
+    TEXTURE,
+    OUT_OF_MEMORY,
+    CONTEXT_LOST
 };
 mod pointers; pub use pointers::*;
 mod raii; pub use raii::*;
 mod error; pub use error::*;
 mod traits; pub use traits::*;
 mod impls; pub use impls::*;
+mod debug; pub use debug::*;
 
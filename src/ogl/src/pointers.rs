@@ -5,7 +5,11 @@ use super::{bindings, traits};
 // ------------------------------------------------------------
 
 #[derive(Clone)]
-pub struct FunctionPointers(Rc<bindings::Gl>);
+pub struct FunctionPointers
+{
+    gl: Rc<bindings::Gl>,
+    debug: bool
+}
 
 impl FunctionPointers
 {    
@@ -16,7 +20,42 @@ impl FunctionPointers
         // This is enabled by default on some hardware,
         // disabling it for consistency
         unsafe{pointers.Disable(bindings::FRAMEBUFFER_SRGB)}
-        Self(Rc::new(pointers))
+        let debug = Self::has_debug(&pointers);
+        Self{gl: Rc::new(pointers), debug}
+    }
+
+    // Debug output and object labels need OpenGL 4.3 or KHR_debug, loaders
+    // may hand out pointers for functions the context does not support
+    fn has_debug(pointers: &bindings::Gl) -> bool
+    {
+        if !pointers.ObjectLabel.is_loaded() || !pointers.DebugMessageCallback.is_loaded()
+        {
+            return false
+        }
+        let [mut major, mut minor, mut count] = [0; 3];
+        unsafe
+        {
+            pointers.GetIntegerv(bindings::MAJOR_VERSION, &mut major);
+            pointers.GetIntegerv(bindings::MINOR_VERSION, &mut minor);
+            pointers.GetIntegerv(bindings::NUM_EXTENSIONS, &mut count)
+        }
+        let debug = [major, minor] >= [4, 3] || pointers.GetStringi.is_loaded() && (0..count).any
+        (
+            |index|
+            {
+                let name = unsafe{pointers.GetStringi(bindings::EXTENSIONS, index as _)};
+                !name.is_null()
+                    && unsafe{std::ffi::CStr::from_ptr(name as _)}.to_bytes() == b"GL_KHR_debug"
+            }
+        );
+        // Queries past the context's version leave errors behind
+        while unsafe{pointers.GetError()} != bindings::NO_ERROR {}
+        debug
+    }
+
+    pub fn supports_debug(&self) -> bool
+    {
+        self.debug
     }
 }
 
@@ -25,7 +64,7 @@ impl Deref for FunctionPointers
     type Target = bindings::Gl;
     fn deref(&self) -> &Self::Target
     { 
-        &self.0
+        &self.gl
     }
 }
 
@@ -74,6 +113,67 @@ impl FunctionPointers
         }   
     }
 
+    pub fn draw_arrays
+    (
+        &self,
+        mode: bindings::GLenum,
+        first: bindings::GLint,
+        count: bindings::GLsizei
+    ) -> ()
+    {
+        unsafe{self.DrawArrays(mode, first, count)}
+    }
+
+    pub fn dispatch_compute(&self, groups: [bindings::GLuint; 3]) -> ()
+    {
+        unsafe{self.DispatchCompute(groups[0], groups[1], groups[2])}
+    }
+
+    pub fn memory_barrier(&self, barriers: bindings::GLbitfield) -> ()
+    {
+        unsafe{self.MemoryBarrier(barriers)}
+    }
+
+    // RENDERER, VENDOR, VERSION or SHADING_LANGUAGE_VERSION
+    pub fn get_string(&self, name: bindings::GLenum) -> String
+    {
+        let string = unsafe{self.GetString(name)};
+        match string.is_null()
+        {
+            true => String::new(),
+            false => unsafe{std::ffi::CStr::from_ptr(string as _)}
+                .to_string_lossy()
+                .into_owned()
+        }
+    }
+
+    pub fn get_integer(&self, name: bindings::GLenum) -> bindings::GLint
+    {
+        let mut value = 0;
+        unsafe{self.GetIntegerv(name, &mut value)};
+        value
+    }
+
+    pub fn enable(&self, capability: bindings::GLenum) -> ()
+    {
+        unsafe{self.Enable(capability)}
+    }
+
+    pub fn disable(&self, capability: bindings::GLenum) -> ()
+    {
+        unsafe{self.Disable(capability)}
+    }
+
+    pub fn blend_func
+    (
+        &self,
+        source: bindings::GLenum,
+        destination: bindings::GLenum
+    ) -> ()
+    {
+        unsafe{self.BlendFunc(source, destination)}
+    }
+
     pub fn active_texture(&self, unit: bindings::GLenum) -> ()
     {
         unsafe
@@ -1,16 +1,46 @@
 
-use std::ops::Deref;
-use super::{bindings::*, pointers::*};
+use std::
+{
+    ops::Deref,
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering}
+};
+use super::{bindings::*, pointers::*, traits::*};
+
+// ------------------------------------------------------------
+
+// Names the object in debug output, names from Gen* calls only become
+// objects once bound. Does nothing without debug support
+fn object_label
+(
+    pointers: &FunctionPointers,
+    identifier: GLenum,
+    handle: GLuint,
+    label: &str
+) -> ()
+{
+    if !pointers.supports_debug()
+    {
+        return
+    }
+    unsafe
+    {
+        pointers.ObjectLabel
+            (identifier, handle, label.len() as _, label.as_ptr() as _)
+    }
+}
 
 // ------------------------------------------------------------
 
-pub struct VertexBufferObject
+// Buffer objects are bound to the target of their marker type
+pub struct Buffer<T>
 { 
     pointers: FunctionPointers,
-    handle: GLuint
+    handle: GLuint,
+    target: PhantomData<T>
 }
 
-impl Deref for VertexBufferObject
+impl<T> Deref for Buffer<T>
 {
     type Target = GLuint;
     fn deref(&self) -> &Self::Target
@@ -19,27 +49,32 @@ impl Deref for VertexBufferObject
     }
 }
 
-impl VertexBufferObject
+impl<T: BufferTarget> Buffer<T>
 {
     pub fn new(pointers: &FunctionPointers) -> Self
     {
         let mut handle = 0;
         unsafe{pointers.GenBuffers(1, &mut handle)}
-        Self{pointers: pointers.clone(), handle}
+        Self{pointers: pointers.clone(), handle, target: PhantomData}
     }
 
     pub fn pointers(&self) -> &FunctionPointers
     {
         &self.pointers
     }
+
+    pub fn label(&self, label: &str) -> ()
+    {
+        object_label(&self.pointers, BUFFER, **self, label)
+    }
     
     pub fn bind(&self) -> ()
     {
-        unsafe{self.pointers.BindBuffer(ARRAY_BUFFER, **self)}
+        unsafe{self.pointers.BindBuffer(T::TARGET, **self)}
     }
 }
 
-impl Drop for VertexBufferObject
+impl<T> Drop for Buffer<T>
 {
     fn drop(&mut self) -> ()
     {
@@ -47,6 +82,13 @@ impl Drop for VertexBufferObject
     }
 }
 
+pub type VertexBufferObject = Buffer<ArrayTarget>;
+pub type ElementBufferObject = Buffer<ElementArrayTarget>;
+pub type UniformBuffer = Buffer<UniformTarget>;
+pub type ShaderStorageBuffer = Buffer<ShaderStorageTarget>;
+pub type PixelPackBuffer = Buffer<PixelPackTarget>;
+pub type PixelUnpackBuffer = Buffer<PixelUnpackTarget>;
+
 // ------------------------------------------------------------
 
 pub struct VertexArrayObject
@@ -78,6 +120,11 @@ impl VertexArrayObject
         &self.pointers
     }
 
+    pub fn label(&self, label: &str) -> ()
+    {
+        object_label(&self.pointers, VERTEX_ARRAY, **self, label)
+    }
+
     pub fn bind(&self) -> ()
     {
         unsafe{self.pointers.BindVertexArray(**self)}
@@ -128,6 +175,11 @@ impl Shader
     {
         &self.pointers
     }
+
+    pub fn label(&self, label: &str) -> ()
+    {
+        object_label(&self.pointers, SHADER, **self, label)
+    }
 }
 
 impl Drop for Shader
@@ -143,7 +195,8 @@ impl Drop for Shader
 pub struct Program
 {
     pointers: FunctionPointers,
-    handle: GLuint
+    handle: GLuint,
+    generation: u64
 }
 
 impl Deref for Program
@@ -159,17 +212,31 @@ impl Program
 {
     pub fn new(pointers: &FunctionPointers) -> Self
     {
+        // Program names get recycled by the driver, the
+        // generation tells apart successive program objects
+        static GENERATION: AtomicU64 = AtomicU64::new(0);
         Self
         {
             pointers: pointers.clone(), 
-            handle: unsafe{pointers.CreateProgram()}
+            handle: unsafe{pointers.CreateProgram()},
+            generation: GENERATION.fetch_add(1, Ordering::Relaxed)
         }
     }
 
+    pub fn generation(&self) -> u64
+    {
+        self.generation
+    }
+
     pub fn pointers(&self) -> &FunctionPointers
     {
         &self.pointers
     }
+
+    pub fn label(&self, label: &str) -> ()
+    {
+        object_label(&self.pointers, PROGRAM, **self, label)
+    }
     
     pub fn r#use(&self) -> ()
     {
@@ -215,6 +282,11 @@ impl Texture
     {
         &self.pointers
     }
+
+    pub fn label(&self, label: &str) -> ()
+    {
+        object_label(&self.pointers, TEXTURE, **self, label)
+    }
     
     pub fn bind(&self) -> ()
     {
@@ -261,6 +333,11 @@ impl FramebufferObject
         &self.pointers
     }
 
+    pub fn label(&self, label: &str) -> ()
+    {
+        object_label(&self.pointers, FRAMEBUFFER, **self, label)
+    }
+
     pub fn bind(&self) -> ()
     {
         unsafe{self.pointers.BindFramebuffer(FRAMEBUFFER, **self)}
@@ -275,3 +352,147 @@ impl Drop for FramebufferObject
     }
 }
 
+
+// ------------------------------------------------------------
+
+pub struct Renderbuffer
+{
+    pointers: FunctionPointers,
+    handle: GLuint
+}
+
+impl Deref for Renderbuffer
+{
+    type Target = GLuint;
+    fn deref(&self) -> &Self::Target
+    {
+        &self.handle
+    }
+}
+
+impl Renderbuffer
+{
+    pub fn new(pointers: &FunctionPointers) -> Self
+    {
+        let mut handle = 0;
+        unsafe{pointers.GenRenderbuffers(1, &mut handle)}
+        Self{pointers: pointers.clone(), handle}
+    }
+
+    pub fn pointers(&self) -> &FunctionPointers
+    {
+        &self.pointers
+    }
+
+    pub fn label(&self, label: &str) -> ()
+    {
+        object_label(&self.pointers, RENDERBUFFER, **self, label)
+    }
+
+    pub fn bind(&self) -> ()
+    {
+        unsafe{self.pointers.BindRenderbuffer(RENDERBUFFER, **self)}
+    }
+}
+
+impl Drop for Renderbuffer
+{
+    fn drop(&mut self) -> ()
+    {
+        unsafe{self.pointers.DeleteRenderbuffers(1, &**self)}
+    }
+}
+
+// ------------------------------------------------------------
+
+pub struct FenceSync
+{
+    pointers: FunctionPointers,
+    handle: GLsync
+}
+
+impl Deref for FenceSync
+{
+    type Target = GLsync;
+    fn deref(&self) -> &Self::Target
+    {
+        &self.handle
+    }
+}
+
+impl FenceSync
+{
+    // Signaled once every command issued before it has completed
+    pub fn new(pointers: &FunctionPointers) -> Self
+    {
+        let handle = unsafe{pointers.FenceSync(SYNC_GPU_COMMANDS_COMPLETE, 0)};
+        Self{pointers: pointers.clone(), handle}
+    }
+
+    pub fn pointers(&self) -> &FunctionPointers
+    {
+        &self.pointers
+    }
+
+    pub fn label(&self, label: &str) -> ()
+    {
+        unsafe
+        {
+            self.pointers.ObjectPtrLabel
+                (**self as _, label.len() as _, label.as_ptr() as _)
+        }
+    }
+}
+
+impl Drop for FenceSync
+{
+    fn drop(&mut self) -> ()
+    {
+        unsafe{self.pointers.DeleteSync(**self)}
+    }
+}
+
+// ------------------------------------------------------------
+
+pub struct Query
+{
+    pointers: FunctionPointers,
+    handle: GLuint
+}
+
+impl Deref for Query
+{
+    type Target = GLuint;
+    fn deref(&self) -> &Self::Target
+    {
+        &self.handle
+    }
+}
+
+impl Query
+{
+    pub fn new(pointers: &FunctionPointers) -> Self
+    {
+        let mut handle = 0;
+        unsafe{pointers.GenQueries(1, &mut handle)}
+        Self{pointers: pointers.clone(), handle}
+    }
+
+    pub fn pointers(&self) -> &FunctionPointers
+    {
+        &self.pointers
+    }
+
+    pub fn label(&self, label: &str) -> ()
+    {
+        object_label(&self.pointers, QUERY, **self, label)
+    }
+}
+
+impl Drop for Query
+{
+    fn drop(&mut self) -> ()
+    {
+        unsafe{self.pointers.DeleteQueries(1, &**self)}
+    }
+}
@@ -27,11 +27,20 @@ impl IndicesDataType for GLuint
 
 pub trait ToUniform
 {
+    const UNIFORM_TYPE: GLenum;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ();
+
+    fn accepts(kind: GLenum) -> bool
+    {
+        kind == Self::UNIFORM_TYPE
+    }
 }
 
 impl ToUniform for GLfloat
 {
+    const UNIFORM_TYPE: GLenum = FLOAT;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform1fv(location, 1, self)}
@@ -40,6 +49,8 @@ impl ToUniform for GLfloat
 
 impl ToUniform for [GLfloat; 2]
 {
+    const UNIFORM_TYPE: GLenum = FLOAT_VEC2;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform2fv(location, 1, self.as_ptr())}
@@ -48,6 +59,8 @@ impl ToUniform for [GLfloat; 2]
 
 impl ToUniform for [GLfloat; 3]
 {
+    const UNIFORM_TYPE: GLenum = FLOAT_VEC3;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform3fv(location, 1, self.as_ptr())}
@@ -56,6 +69,8 @@ impl ToUniform for [GLfloat; 3]
 
 impl ToUniform for [GLfloat; 4]
 {
+    const UNIFORM_TYPE: GLenum = FLOAT_VEC4;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform4fv(location, 1, self.as_ptr())}
@@ -64,6 +79,8 @@ impl ToUniform for [GLfloat; 4]
 
 impl ToUniform for [GLfloat; 9]
 {
+    const UNIFORM_TYPE: GLenum = FLOAT_MAT3;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.UniformMatrix3fv(location, 1, FALSE, self.as_ptr())}
@@ -72,6 +89,8 @@ impl ToUniform for [GLfloat; 9]
 
 impl ToUniform for [GLfloat; 16]
 {
+    const UNIFORM_TYPE: GLenum = FLOAT_MAT4;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.UniformMatrix4fv(location, 1, FALSE, self.as_ptr())}
@@ -80,14 +99,25 @@ impl ToUniform for [GLfloat; 16]
 
 impl ToUniform for GLint
 {
+    const UNIFORM_TYPE: GLenum = INT;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform1iv(location, 1, self)}
     }
+
+    fn accepts(kind: GLenum) -> bool
+    {
+        kind == Self::UNIFORM_TYPE
+            || kind == BOOL
+            || super::impls::is_sampler(kind)
+    }
 }
 
 impl ToUniform for [GLint; 2]
 {
+    const UNIFORM_TYPE: GLenum = INT_VEC2;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform2iv(location, 1, self.as_ptr())}
@@ -96,6 +126,8 @@ impl ToUniform for [GLint; 2]
 
 impl ToUniform for [GLint; 3]
 {
+    const UNIFORM_TYPE: GLenum = INT_VEC3;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform3iv(location, 1, self.as_ptr())}
@@ -104,6 +136,8 @@ impl ToUniform for [GLint; 3]
 
 impl ToUniform for [GLint; 4]
 {
+    const UNIFORM_TYPE: GLenum = INT_VEC4;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform4iv(location, 1, self.as_ptr())}
@@ -112,6 +146,8 @@ impl ToUniform for [GLint; 4]
 
 impl ToUniform for GLuint
 {
+    const UNIFORM_TYPE: GLenum = UNSIGNED_INT;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform1uiv(location, 1, self)}
@@ -120,6 +156,8 @@ impl ToUniform for GLuint
 
 impl ToUniform for [GLuint; 2]
 {
+    const UNIFORM_TYPE: GLenum = UNSIGNED_INT_VEC2;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform2uiv(location, 1, self.as_ptr())}
@@ -128,6 +166,8 @@ impl ToUniform for [GLuint; 2]
 
 impl ToUniform for [GLuint; 3]
 {
+    const UNIFORM_TYPE: GLenum = UNSIGNED_INT_VEC3;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform3uiv(location, 1, self.as_ptr())}
@@ -136,12 +176,25 @@ impl ToUniform for [GLuint; 3]
 
 impl ToUniform for [GLuint; 4]
 {
+    const UNIFORM_TYPE: GLenum = UNSIGNED_INT_VEC4;
+
     fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
     {
         unsafe{pointers.Uniform4uiv(location, 1, self.as_ptr())}
     }
 }
 
+// Uploads a whole array uniform
+impl ToUniform for Vec<GLint>
+{
+    const UNIFORM_TYPE: GLenum = INT;
+
+    fn to_uniform(&self, pointers: &FunctionPointers, location: GLint) -> ()
+    {
+        unsafe{pointers.Uniform1iv(location, self.len() as _, self.as_ptr())}
+    }
+}
+
 // ------------------------------------------------------------
 
 pub trait Zero
@@ -273,3 +326,236 @@ impl TextureComponentDataType for GLfloat
     const TYPE_ENUM: GLenum = FLOAT;
 }
 
+
+// ------------------------------------------------------------
+
+pub trait BufferTarget
+{
+    const TARGET: GLenum;
+    const USAGE: GLenum;
+}
+
+// Targets with indexed binding points shared between programs
+pub trait IndexedBufferTarget: BufferTarget {}
+
+pub struct ArrayTarget;
+
+impl BufferTarget for ArrayTarget
+{
+    const TARGET: GLenum = ARRAY_BUFFER;
+    const USAGE: GLenum = STATIC_DRAW;
+}
+
+pub struct ElementArrayTarget;
+
+impl BufferTarget for ElementArrayTarget
+{
+    const TARGET: GLenum = ELEMENT_ARRAY_BUFFER;
+    const USAGE: GLenum = STATIC_DRAW;
+}
+
+pub struct UniformTarget;
+
+impl BufferTarget for UniformTarget
+{
+    const TARGET: GLenum = UNIFORM_BUFFER;
+    const USAGE: GLenum = DYNAMIC_DRAW;
+}
+
+impl IndexedBufferTarget for UniformTarget {}
+
+pub struct ShaderStorageTarget;
+
+impl BufferTarget for ShaderStorageTarget
+{
+    const TARGET: GLenum = SHADER_STORAGE_BUFFER;
+    const USAGE: GLenum = DYNAMIC_COPY;
+}
+
+impl IndexedBufferTarget for ShaderStorageTarget {}
+
+pub struct PixelPackTarget;
+
+impl BufferTarget for PixelPackTarget
+{
+    const TARGET: GLenum = PIXEL_PACK_BUFFER;
+    const USAGE: GLenum = STREAM_READ;
+}
+
+pub struct PixelUnpackTarget;
+
+impl BufferTarget for PixelUnpackTarget
+{
+    const TARGET: GLenum = PIXEL_UNPACK_BUFFER;
+    const USAGE: GLenum = STREAM_DRAW;
+}
+
+// ------------------------------------------------------------
+
+// Base alignment and size in bytes of a block member, matrices are stored as
+// column vectors padded to a vec4
+pub trait BlockMember
+{
+    const ALIGNMENT: usize;
+    const SIZE: usize;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ();
+}
+
+impl BlockMember for GLfloat
+{
+    const ALIGNMENT: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        bytes.extend(self.to_ne_bytes())
+    }
+}
+
+impl BlockMember for GLint
+{
+    const ALIGNMENT: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        bytes.extend(self.to_ne_bytes())
+    }
+}
+
+impl BlockMember for GLuint
+{
+    const ALIGNMENT: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        bytes.extend(self.to_ne_bytes())
+    }
+}
+
+impl BlockMember for [GLfloat; 2]
+{
+    const ALIGNMENT: usize = 8;
+    const SIZE: usize = 8;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLfloat; 3]
+{
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 12;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLfloat; 4]
+{
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 16;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLint; 2]
+{
+    const ALIGNMENT: usize = 8;
+    const SIZE: usize = 8;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLint; 3]
+{
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 12;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLint; 4]
+{
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 16;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLuint; 2]
+{
+    const ALIGNMENT: usize = 8;
+    const SIZE: usize = 8;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLuint; 3]
+{
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 12;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLuint; 4]
+{
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 16;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
+
+impl BlockMember for [GLfloat; 9]
+{
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 48;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        for column in self.chunks(3)
+        {
+            column.iter().for_each(|value| value.write(bytes));
+            bytes.extend([0; 4])
+        }
+    }
+}
+
+impl BlockMember for [GLfloat; 16]
+{
+    const ALIGNMENT: usize = 16;
+    const SIZE: usize = 64;
+
+    fn write(&self, bytes: &mut Vec<u8>) -> ()
+    {
+        self.iter().for_each(|value| value.write(bytes))
+    }
+}
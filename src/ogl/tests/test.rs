@@ -227,6 +227,83 @@ fn location_not_found() -> ()
 
 // ------------------------------------------------------------
 
+fn active_uniforms() -> ()
+{
+    #[allow(unused_variables)]
+    let (_, context, pointers) = init();
+    let (program, ..) = quad
+    (
+        &pointers,
+        &"
+        #version 460
+        out vec4 color;
+        uniform vec3 u;
+        uniform float v[2];
+        uniform sampler2D t;
+        uniform float unused;
+        void main()
+        {
+            color = vec4(u, v[1]) + texture(t, vec2(0.5));
+        }
+        \0"
+    );
+    let mut uniforms = program.active_uniforms();
+    uniforms.sort_by(|a, b| a.name.cmp(&b.name));
+    let summary: Vec<(&str, &str, GLint)> = uniforms.iter()
+        .map(|u| (u.name.as_str(), u.type_name(), u.size))
+        .collect();
+    assert_eq!(summary, [("t", "sampler2D", 1), ("u", "vec3", 1), ("v", "float", 2)]);
+    for uniform in &uniforms
+    {
+        assert_eq!
+        (
+            uniform.location,
+            program.location(&uniform.name, LocationOf::Uniform).unwrap()
+        )
+    }
+    let attributes = program.active_attributes();
+    assert_eq!(attributes.len(), 1);
+    assert_eq!(attributes[0].name, "corners");
+    assert_eq!(attributes[0].type_name(), "vec2");
+    assert!(GLint::accepts(uniforms[0].kind));
+    assert!(!GLfloat::accepts(uniforms[1].kind));
+    assert!(<[GLfloat; 3]>::accepts(uniforms[1].kind))
+}
+
+// ------------------------------------------------------------
+
+fn uniform_handle() -> ()
+{
+    #[allow(unused_variables)]
+    let (_, context, pointers) = init();
+    let source = "
+        #version 100
+        precision mediump float;
+        uniform vec3 u;
+        void main()
+        {
+            gl_FragColor = vec4(u, 1.0);
+        }
+        \0";
+    let (program, ..) = quad(&pointers, source);
+    program.uniform::<GLfloat>("u").map(|_|()).unwrap_err();
+    program.uniform::<[GLfloat; 3]>("nil").map(|_|()).unwrap_err();
+    let handle = program.uniform::<[GLfloat; 3]>("u").unwrap();
+    program.r#use();
+    handle.set(&program, [1.0, 2.0, 3.0]).unwrap();
+    let (relinked, ..) = quad(&pointers, source);
+    assert!
+    (
+        matches!
+        (
+            handle.set(&relinked, [1.0, 2.0, 3.0]),
+            Err(OGLError::StaleUniformHandle(_))
+        )
+    )
+}
+
+// ------------------------------------------------------------
+
 fn write_read_uniform() -> ()
 {
     #[allow(unused_variables)]
@@ -295,6 +372,74 @@ fn render_to_framebuffer() -> ()
 
 // ------------------------------------------------------------
 
+fn render_with_depth() -> ()
+{
+    #[allow(unused_variables)]
+    let (_, context, pointers) = init();
+    let program = Program::link
+    (
+        &pointers,
+        &[
+            &Shader::compile
+            (
+                &pointers,
+                VERTEX_SHADER,
+                "
+                #version 330 core
+                out vec4 v_color;
+                void main()
+                {
+                    vec2 corners[3] = vec2[](vec2(-4.0), vec2(4.0, -4.0), vec2(-4.0, 4.0));
+                    float depth = gl_VertexID < 3 ? 0.5 : -0.5;
+                    gl_Position = vec4(corners[gl_VertexID % 3], depth, 1.0);
+                    v_color = vec4(gl_VertexID < 3 ? 1.0 : 0.2);
+                }
+                \0"
+            ).unwrap(),
+            &Shader::compile
+            (
+                &pointers,
+                FRAGMENT_SHADER,
+                "
+                #version 330 core
+                in vec4 v_color;
+                out vec4 color;
+                void main()
+                {
+                    color = v_color;
+                }
+                \0"
+            ).unwrap()
+        ]
+    ).unwrap();
+    let texture = texture_8bit(&pointers, None);
+    let fbo = FramebufferObject::new(&pointers);
+    fbo.bind();
+    fbo.attach_color(&texture).unwrap();
+    let depth = Renderbuffer::new(&pointers);
+    depth.bind();
+    depth.storage(DEPTH_COMPONENT24, [1, 1]);
+    fbo.attach_depth(&depth).unwrap();
+    let vao = VertexArrayObject::new(&pointers);
+    vao.bind();
+    program.r#use();
+    let (origin, resolution) = ([0, 0], [1, 1]);
+    pointers.viewport(origin, resolution);
+    pointers.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+    pointers.enable(DEPTH_TEST);
+    pointers.draw_arrays(TRIANGLES, 0, 6);
+    pointers.disable(DEPTH_TEST);
+    let output_pixel = pointers.read_framebuffer::<u8>
+    (
+        origin,
+        [resolution[0] as _, resolution[1] as _],
+        ChannelCount::One
+    ).unwrap();
+    assert_eq!(output_pixel, [51])
+}
+
+// ------------------------------------------------------------
+
 fn framebuffer_ping_pong() -> ()
 {
     #[allow(unused_variables)]
@@ -350,6 +495,258 @@ fn framebuffer_ping_pong() -> ()
 
 // ------------------------------------------------------------
 
+fn compute_dispatch() -> ()
+{
+    #[allow(unused_variables)]
+    let (_, context, pointers) = init();
+    let compute = Program::link
+    (
+        &pointers,
+        &[
+            &Shader::compile
+            (
+                &pointers,
+                COMPUTE_SHADER,
+                "
+                #version 430
+                layout(local_size_x = 1) in;
+                layout(rgba32f, binding = 0) uniform image2D image;
+                layout(std430, binding = 0) buffer State {vec4 value;};
+                void main()
+                {
+                    value = vec4(0.0, 1.0, 0.0, 1.0);
+                    imageStore(image, ivec2(0), vec4(1.0, 0.0, 1.0, 1.0));
+                }
+                \0"
+            ).unwrap()
+        ]
+    ).unwrap();
+    let texture = Texture::new(&pointers);
+    texture.bind();
+    texture.setup
+    (
+        None,
+        InterpolationType::Nearest,
+        InterpolationType::Nearest,
+        None
+    );
+    texture.fill
+    (
+        Image::<GLfloat>
+        {
+            data: None,
+            resolution: [1; 2],
+            channels: ChannelCount::Four
+        },
+        false
+    );
+    let buffer = ShaderStorageBuffer::new(&pointers);
+    buffer.bind();
+    buffer.allocate(16);
+    compute.r#use();
+    texture.bind_image(0, ImageAccess::WriteOnly, RGBA32F);
+    buffer.bind_base(0);
+    pointers.dispatch_compute([1; 3]);
+    pointers.memory_barrier
+    (
+        TEXTURE_FETCH_BARRIER_BIT | SHADER_STORAGE_BARRIER_BIT
+    );
+    #[allow(unused_variables)]
+    let (program, vao, corners, indices) = quad
+    (
+        &pointers,
+        &"
+        #version 430
+        out vec4 color;
+        uniform sampler2D t;
+        layout(std430, binding = 0) buffer State {vec4 value;};
+        void main()
+        {
+            color = texture(t, vec2(0.5)) + value;
+        }
+        \0"
+    );
+    let (origin, resolution) = ([0, 0], [1, 1]);
+    program.r#use();
+    texture.bind();
+    pointers.viewport(origin, resolution);
+    pointers.draw_elements(TRIANGLES, &indices);
+    let output_pixel = pointers.read_framebuffer::<u8>
+    (
+        origin,
+        [resolution[0] as _, resolution[1] as _],
+        ChannelCount::Four
+    ).unwrap();
+    assert_eq!(output_pixel, vec![255; 4])
+}
+
+// ------------------------------------------------------------
+
+fn block_layout() -> ()
+{
+    let mut std140 = BlockWriter::new(BlockLayout::Std140);
+    std140.push(1.0f32).push([2.0f32; 3]).push_array(&[3.0f32, 4.0]).push(5u32);
+    let mut std430 = BlockWriter::new(BlockLayout::Std430);
+    std430.push(1.0f32).push([2.0f32; 3]).push_array(&[3.0f32, 4.0]).push(5u32);
+    // vec3 at 16, the float array stride is 16 in std140 and 4 in std430
+    assert_eq!(std140.bytes().len(), 16 + 12 + 4 + 32 + 4);
+    assert_eq!(std430.bytes().len(), 16 + 12 + 8 + 4);
+    assert_eq!(&std140.bytes()[48..52], &4.0f32.to_ne_bytes());
+    assert_eq!(&std430.bytes()[32..36], &4.0f32.to_ne_bytes());
+    // Blocks end on their base alignment, the vec3 one in both layouts
+    assert_eq!(std140.finish().len(), 80);
+    assert_eq!(std430.finish().len(), 48);
+    let mut matrix = BlockWriter::new(BlockLayout::Std430);
+    matrix.push(1.0f32).push([1.0f32; 9]);
+    assert_eq!(matrix.bytes().len(), 16 + 48);
+    let mut scalars = BlockWriter::new(BlockLayout::Std430);
+    scalars.push(1.0f32).push(2u32).push(3.0f32);
+    assert_eq!(scalars.finish().len(), 12)
+}
+
+// ------------------------------------------------------------
+
+fn uniform_block() -> ()
+{
+    #[allow(unused_variables)]
+    let (_, context, pointers) = init();
+    #[allow(unused_variables)]
+    let (program, vao, corners, indices) = quad
+    (
+        &pointers,
+        &"
+        #version 460
+        out vec4 color;
+        layout(std140) uniform Colors
+        {
+            float red;
+            vec3 green_blue;
+        };
+        void main()
+        {
+            color = vec4(red, green_blue);
+        }
+        \0"
+    );
+    program.uniform_block_binding("Nil", 2).unwrap_err();
+    program.uniform_block_binding("Colors", 2).unwrap();
+    let mut block = BlockWriter::new(BlockLayout::Std140);
+    block.push(1.0f32).push([0.0f32, 1.0, 1.0]);
+    let buffer = UniformBuffer::new(&pointers);
+    buffer.bind();
+    buffer.fill(&block.finish());
+    assert_eq!(buffer.size(), 32);
+    buffer.sub_data(0, &[0.0f32]).unwrap();
+    buffer.bind_base(2);
+    let (origin, resolution) = ([0, 0], [1, 1]);
+    program.r#use();
+    pointers.viewport(origin, resolution);
+    pointers.draw_elements(TRIANGLES, &indices);
+    let output_pixel = pointers.read_framebuffer::<u8>
+    (
+        origin,
+        [resolution[0] as _, resolution[1] as _],
+        ChannelCount::Four
+    ).unwrap();
+    assert_eq!(output_pixel, vec![0, 255, 255, 255])
+}
+
+// ------------------------------------------------------------
+
+fn buffer_mapping() -> ()
+{
+    #[allow(unused_variables)]
+    let (_, context, pointers) = init();
+    let buffer = ShaderStorageBuffer::new(&pointers);
+    buffer.bind();
+    buffer.allocate(4 * std::mem::size_of::<GLuint>());
+    {
+        let mut mapping = buffer.map::<GLuint>(MapAccess::Write).unwrap();
+        assert_eq!(mapping.len(), 4);
+        mapping.copy_from_slice(&[1, 2, 3, 4]);
+    }
+    assert_eq!(buffer.get_sub_data::<GLuint>(4, 2).unwrap(), vec![2, 3]);
+    let mapping = buffer.map::<GLuint>(MapAccess::Read).unwrap();
+    assert_eq!(*mapping, [1, 2, 3, 4])
+}
+
+// ------------------------------------------------------------
+
+fn readback_ring() -> ()
+{
+    #[allow(unused_variables)]
+    let (window, context, pointers) = init();
+    let (origin, resolution) = ([0, 0], [1, 1]);
+    pointers.viewport(origin, resolution);
+    let mut ring = ReadbackRing::<u8>::new(&pointers, 2);
+    let mut received = vec!();
+    for value in [0.0, 1.0, 0.0]
+    {
+        #[allow(unused_variables)]
+        let (program, vao, corners, indices) = color_quad
+        (
+            &pointers,
+            (value, value, value, 1.0)
+        );
+        program.r#use();
+        pointers.draw_elements(TRIANGLES, &indices);
+        let pixels = ring.request
+        (
+            origin,
+            [resolution[0] as _, resolution[1] as _],
+            ChannelCount::One
+        ).unwrap();
+        received.push(pixels);
+    }
+    assert_eq!(received, vec![None, None, Some(vec![0])]);
+    assert_eq!(ring.flush().unwrap(), vec![vec![255], vec![0]]);
+    let fence = FenceSync::new(&pointers);
+    assert!(fence.client_wait(std::time::Duration::from_secs(1)).unwrap())
+}
+
+// ------------------------------------------------------------
+
+fn object_label() -> ()
+{
+    #[allow(unused_variables)]
+    let (_, context, pointers) = init();
+    let messages = std::rc::Rc::new(std::cell::RefCell::new(vec!()));
+    let received = messages.clone();
+    let debug = DebugOutput::new
+    (
+        &pointers,
+        DebugSeverity::Medium,
+        move |message| received.borrow_mut().push((message.severity, message.message))
+    ).expect("The test context supports KHR_debug");
+    debug.insert(DebugSeverity::High, "high");
+    debug.insert(DebugSeverity::Medium, "medium");
+    debug.insert(DebugSeverity::Low, "low");
+    // Messages are synchronous, the low one is below the minimum
+    assert_eq!
+    (
+        *messages.borrow(),
+        vec!
+        [
+            (DebugSeverity::High, String::from("high")),
+            (DebugSeverity::Medium, String::from("medium"))
+        ]
+    );
+    let texture = texture_8bit(&pointers, None);
+    texture.label("texture noise");
+    let mut label = [0u8; 32];
+    let mut length = 0;
+    unsafe
+    {
+        pointers.GetObjectLabel
+            (TEXTURE, *texture, 32, &mut length, label.as_mut_ptr() as _)
+    }
+    assert_eq!(&label[..length as usize], b"texture noise");
+    drop(debug);
+    pointers.clear_errors()
+}
+
+// ------------------------------------------------------------
+
 fn main() -> ()
 {
     program_compiles();
@@ -361,8 +758,17 @@ fn main() -> ()
     write_read_multiple_texture();
     location_not_found();
     location_found();
+    active_uniforms();
+    uniform_handle();
     write_read_uniform();
     render_to_framebuffer();
-    framebuffer_ping_pong()
+    render_with_depth();
+    framebuffer_ping_pong();
+    compute_dispatch();
+    block_layout();
+    uniform_block();
+    buffer_mapping();
+    readback_ring();
+    object_label()
 }
 
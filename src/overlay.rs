@@ -0,0 +1,222 @@
+use std::time::{Duration, Instant};
+use super::{window::Window, logger};
+
+// ------------------------------------------------------------
+
+// Window rectangle as [x, y, width, height] from the top left corner
+pub type Region = [u32; 4];
+
+// Cells with any visible pixel, merged into horizontal runs. Alpha rows
+// start from the bottom like OpenGL's, regions from the top like windows'
+fn opaque_regions(alpha: &[u8], resolution: [u32; 2], cell: u32) -> Vec<Region>
+{
+    let [width, height] = resolution;
+    let visible = |column: u32, row: u32|
+    {
+        let [left, bottom] = [column * cell, row * cell];
+        (bottom..(bottom + cell).min(height)).any
+        (
+            |y| (left..(left + cell).min(width))
+                .any(|x| alpha[(y * width + x) as usize] > 0)
+        )
+    };
+    let mut regions = vec!();
+    for row in 0..height.div_ceil(cell)
+    {
+        let top = height.saturating_sub((row + 1) * cell);
+        let bottom = height - row * cell;
+        let mut start = None;
+        for column in 0..=width.div_ceil(cell)
+        {
+            let visible = column < width.div_ceil(cell) && visible(column, row);
+            match (visible, start)
+            {
+                (true, None) => start = Some(column),
+                (false, Some(first)) =>
+                {
+                    let left = first * cell;
+                    let right = (column * cell).min(width);
+                    regions.push([left, top, right - left, bottom - top]);
+                    start = None
+                }
+                _ => {}
+            }
+        }
+    }
+    regions
+}
+
+// ------------------------------------------------------------
+
+#[cfg(any
+(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod input
+{
+    use x11_dl::{xlib, xfixes};
+    use super::{Region, Window};
+
+    // ShapeInput from the X shape extension
+    const SHAPE_INPUT: i32 = 2;
+
+    // The input shape of an X11 window, set through XFixes
+    pub struct InputShape
+    {
+        xlib: xlib::Xlib,
+        // x11-dl names the XFixes library Xlib too
+        xfixes: xfixes::Xlib,
+        display: *mut xlib::Display,
+        window: xlib::Window
+    }
+
+    impl InputShape
+    {
+        pub fn new(window: &Window) -> anyhow::Result<Self>
+        {
+            let (display, window) = window.xlib()
+                .ok_or_else(|| anyhow::anyhow!("Click-through is only supported on X11"))?;
+            let display = display as *mut xlib::Display;
+            let xlib = xlib::Xlib::open()?;
+            let xfixes = xfixes::Xlib::open()?;
+            let [mut major, mut minor] = [5, 0];
+            if unsafe{(xfixes.XFixesQueryVersion)(display, &mut major, &mut minor)} == 0
+            {
+                anyhow::bail!("The X server does not support XFixes")
+            }
+            Ok(Self{xlib, xfixes, display, window})
+        }
+
+        // The whole window receives input without regions
+        pub fn set(&self, regions: Option<&[Region]>) -> ()
+        {
+            unsafe
+            {
+                let region = match regions
+                {
+                    Some(regions) =>
+                    {
+                        let mut rectangles: Vec<xlib::XRectangle> = regions.iter().map
+                        (
+                            |&[x, y, width, height]| xlib::XRectangle
+                            {
+                                x: x as _,
+                                y: y as _,
+                                width: width as _,
+                                height: height as _
+                            }
+                        ).collect();
+                        (self.xfixes.XFixesCreateRegion)
+                            (self.display, rectangles.as_mut_ptr(), rectangles.len() as _)
+                    }
+                    None => 0
+                };
+                (self.xfixes.XFixesSetWindowShapeRegion)
+                    (self.display, self.window, SHAPE_INPUT, 0, 0, region);
+                if region != 0
+                {
+                    (self.xfixes.XFixesDestroyRegion)(self.display, region)
+                }
+                (self.xlib.XFlush)(self.display);
+            }
+        }
+    }
+}
+
+#[cfg(not(any
+(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+mod input
+{
+    use super::{Region, Window};
+
+    pub struct InputShape;
+
+    impl InputShape
+    {
+        pub fn new(_window: &Window) -> anyhow::Result<Self>
+        {
+            anyhow::bail!("Click-through is only supported on X11")
+        }
+
+        pub fn set(&self, _regions: Option<&[Region]>) -> ()
+        {
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+// Keeps the window on top and lets clicks through where the canvas is fully
+// transparent. Regions follow the alpha read back a few times per second
+pub struct Overlay
+{
+    shape: Option<input::InputShape>,
+    updated: Option<Instant>
+}
+
+impl Overlay
+{
+    const CELL: u32 = 8;
+    const INTERVAL: Duration = Duration::from_millis(200);
+
+    pub fn new(window: &Window) -> Self
+    {
+        window.set_always_on_top(true);
+        let shape = input::InputShape::new(window)
+            .map_err(|error| logger::warning(format!("Overlay: {error}")))
+            .ok();
+        Self{shape, updated: None}
+    }
+
+    pub fn due(&self) -> bool
+    {
+        self.shape.is_some()
+            && self.updated.is_none_or(|updated| updated.elapsed() >= Self::INTERVAL)
+    }
+
+    pub fn update(&mut self, alpha: &[u8], resolution: [u32; 2]) -> ()
+    {
+        if let Some(shape) = &self.shape
+        {
+            shape.set(Some(&opaque_regions(alpha, resolution, Self::CELL)));
+            self.updated = Some(Instant::now())
+        }
+    }
+
+    pub fn close(self, window: &Window) -> ()
+    {
+        if let Some(shape) = &self.shape
+        {
+            shape.set(None)
+        }
+        window.set_always_on_top(false)
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn regions() -> ()
+{
+    // 20x10 with visible pixels in the bottom left and top right corners
+    let mut alpha = vec![0u8; 200];
+    alpha[0] = 255;
+    alpha[9 * 20 + 19] = 1;
+    assert_eq!
+    (
+        opaque_regions(&alpha, [20, 10], 8),
+        [[0, 2, 8, 8], [16, 0, 4, 2]]
+    );
+    assert_eq!(opaque_regions(&[255; 200], [20, 10], 8), [[0, 2, 20, 8], [0, 0, 20, 2]]);
+    assert!(opaque_regions(&[0; 200], [20, 10], 8).is_empty())
+}
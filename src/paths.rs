@@ -0,0 +1,183 @@
+use std::{fmt, path::*};
+
+// ------------------------------------------------------------
+
+#[derive(Debug)]
+pub enum PathError
+{
+    UnsetVariable(String),
+    UnclosedVariable(String),
+    NotFound(PathBuf, Vec<PathBuf>)
+}
+
+impl std::error::Error for PathError {}
+
+impl fmt::Display for PathError
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self
+        {
+            Self::UnsetVariable(name)
+                => write!(formatter, "Environment variable `{name}` is not set"),
+            Self::UnclosedVariable(path)
+                => write!(formatter, "Unclosed `${{` in `{path}`"),
+            Self::NotFound(path, searched) =>
+            {
+                write!(formatter, "`{}` not found, searched:", path.display())?;
+                for candidate in searched
+                {
+                    write!(formatter, "\n    {}", candidate.display())?
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+pub type PathResult<T> = std::result::Result<T, PathError>;
+
+// ------------------------------------------------------------
+
+// Expands a leading `~` and `$NAME` or `${NAME}` variables
+fn expand(path: &str, variable: impl Fn(&str) -> Option<String>) -> PathResult<String>
+{
+    let lookup = |name: &str| variable(name)
+        .ok_or_else(|| PathError::UnsetVariable(name.to_string()));
+    let mut expanded = String::new();
+    let mut rest = path;
+    if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\")
+    {
+        let home = variable("HOME")
+            .or_else(|| variable("USERPROFILE"))
+            .ok_or_else(|| PathError::UnsetVariable(String::from("HOME")))?;
+        expanded += &home;
+        rest = &rest[1..]
+    }
+    while let Some(at) = rest.find('$')
+    {
+        expanded += &rest[..at];
+        rest = &rest[at + 1..];
+        match rest.strip_prefix('{')
+        {
+            Some(braced) =>
+            {
+                let end = braced.find('}')
+                    .ok_or_else(|| PathError::UnclosedVariable(path.to_string()))?;
+                expanded += &lookup(&braced[..end])?;
+                rest = &braced[end + 1..]
+            }
+            None =>
+            {
+                let end = match rest.starts_with(|character: char| character.is_ascii_digit())
+                {
+                    true => 0,
+                    false => rest
+                        .find(|character: char| !character.is_ascii_alphanumeric() && character != '_')
+                        .unwrap_or(rest.len())
+                };
+                match end
+                {
+                    0 => expanded.push('$'),
+                    _ => expanded += &lookup(&rest[..end])?
+                }
+                rest = &rest[end..]
+            }
+        }
+    }
+    Ok(expanded + rest)
+}
+
+// ------------------------------------------------------------
+
+// Relative paths are looked up next to the shader first, then in
+// the directories listed in GLIS_PATH, separated like PATH
+pub struct PathResolver
+{
+    root: PathBuf,
+    search: Vec<PathBuf>
+}
+
+impl PathResolver
+{
+    pub fn new(root: impl AsRef<Path>) -> Self
+    {
+        let search = std::env::var_os("GLIS_PATH")
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default();
+        Self::with_search_paths(root, search)
+    }
+
+    pub fn with_search_paths(root: impl AsRef<Path>, search: Vec<PathBuf>) -> Self
+    {
+        Self{root: root.as_ref().to_path_buf(), search}
+    }
+
+    pub fn resolve(&self, path: impl AsRef<Path>) -> PathResult<PathBuf>
+    {
+        let path = path.as_ref();
+        let expanded = PathBuf::from
+        (
+            expand(&path.to_string_lossy(), |name| std::env::var(name).ok())?
+        );
+        let candidates: Vec<PathBuf> = match expanded.is_absolute()
+        {
+            true => vec!(expanded),
+            false => std::iter::once(&self.root)
+                .chain(&self.search)
+                .map(|directory| directory.join(&expanded))
+                .collect()
+        };
+        match candidates.iter().find(|candidate| candidate.is_file())
+        {
+            Some(found) => Ok(found.clone()),
+            None => Err(PathError::NotFound(path.to_path_buf(), candidates))
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn expansion() -> ()
+{
+    let variable = |name: &str| match name
+    {
+        "HOME" => Some(String::from("/home/glis")),
+        "ASSETS" => Some(String::from("/assets")),
+        _ => None
+    };
+    assert_eq!(expand("~/a.png", variable).unwrap(), "/home/glis/a.png");
+    assert_eq!(expand("$ASSETS/a.png", variable).unwrap(), "/assets/a.png");
+    assert_eq!(expand("${ASSETS}_b/$5.png", variable).unwrap(), "/assets_b/$5.png");
+    assert_eq!(expand("a~/b.png", variable).unwrap(), "a~/b.png");
+    assert!(matches!(expand("$MISSING/a.png", variable), Err(PathError::UnsetVariable(_))));
+    assert!(matches!(expand("${ASSETS/a.png", variable), Err(PathError::UnclosedVariable(_))))
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn search() -> ()
+{
+    let directory = std::env::temp_dir().join(format!("glis-paths-{}", std::process::id()));
+    let [root, search] = ["root", "search"].map(|name| directory.join(name));
+    for directory in [&root, &search]
+    {
+        std::fs::create_dir_all(directory).unwrap()
+    }
+    std::fs::write(root.join("a.png"), []).unwrap();
+    std::fs::write(search.join("a.png"), []).unwrap();
+    std::fs::write(search.join("b.png"), []).unwrap();
+    let resolver = PathResolver::with_search_paths(&root, vec!(search.clone()));
+    assert_eq!(resolver.resolve("a.png").unwrap(), root.join("a.png"));
+    assert_eq!(resolver.resolve("b.png").unwrap(), search.join("b.png"));
+    assert_eq!(resolver.resolve(search.join("b.png")).unwrap(), search.join("b.png"));
+    match resolver.resolve("c.png")
+    {
+        Err(PathError::NotFound(_, searched)) =>
+            assert_eq!(searched, [root.join("c.png"), search.join("c.png")]),
+        _ => panic!("c.png should not be found")
+    }
+    std::fs::remove_dir_all(directory).unwrap()
+}
@@ -0,0 +1,212 @@
+use std::{path::*, fs};
+use ogl::*;
+use winit::event_loop::*;
+use super::{app::*, assets::*, canvas::*, glsl::*, timeline::Frame, logger};
+
+// ------------------------------------------------------------
+
+pub struct PosterOptions
+{
+    path: PathBuf,
+    size: Option<[u32; 2]>,
+    tile: u32,
+    frame: Frame,
+    output: Option<PathBuf>
+}
+
+impl PosterOptions
+{
+    pub const USAGE: &'static str =
+        "Usage: glis poster <shader> [--size WIDTH HEIGHT] [--tile SIZE] [--frame N] [--output poster.png]";
+
+    pub fn parse(mut args: impl Iterator<Item = String>) -> anyhow::Result<Self>
+    {
+        let mut path = None;
+        let mut size = None;
+        let mut tile = 2048;
+        let mut frame = 0;
+        let mut output = None;
+        let value = |args: &mut dyn Iterator<Item = String>, flag: &str|
+            args.next().ok_or_else(|| anyhow::anyhow!("Expected a value for `{flag}`"));
+        while let Some(arg) = args.next()
+        {
+            match arg.as_str()
+            {
+                "--size" =>
+                {
+                    let width = value(&mut args, &arg)?.parse()?;
+                    let height = value(&mut args, &arg)?.parse()?;
+                    size = Some([width, height])
+                }
+                "--tile" => tile = value(&mut args, &arg)?.parse()?,
+                "--frame" => frame = value(&mut args, &arg)?.parse()?,
+                "--output" => output = Some(PathBuf::from(value(&mut args, &arg)?)),
+                flag if flag.starts_with("--") => anyhow::bail!
+                    ("Unknown option `{flag}`\n{}", Self::USAGE),
+                _ => path = Some(PathBuf::from(arg))
+            }
+        }
+        match (path, size, tile)
+        {
+            (None, _, _) => anyhow::bail!(Self::USAGE),
+            (_, Some([0, _] | [_, 0]), _) => anyhow::bail!("Expected a poster of at least one pixel"),
+            (_, _, 0) => anyhow::bail!("Expected tiles of at least one pixel"),
+            (Some(path), _, _) => Ok(Self{path, size, tile, frame, output})
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+// Origins of the tiles covering the poster, the last ones may overhang
+fn tiles(size: [u32; 2], tile: [u32; 2]) -> Vec<[u32; 2]>
+{
+    let mut origins = vec!();
+    for y in (0..size[1]).step_by(tile[1] as usize)
+    {
+        for x in (0..size[0]).step_by(tile[0] as usize)
+        {
+            origins.push([x, y])
+        }
+    }
+    origins
+}
+
+// Copies RGBA tile rows from the bottom into poster rows from the top,
+// cropping the guard band around the tile and what overhangs
+fn place
+(
+    poster: &mut [u8],
+    size: [u32; 2],
+    pixels: &[u8],
+    tile: [u32; 2],
+    origin: [u32; 2],
+    guard: u32
+) -> ()
+{
+    let stride = tile[0] + 2 * guard;
+    let width = tile[0].min(size[0] - origin[0]) as usize * 4;
+    for row in 0..tile[1].min(size[1] - origin[1])
+    {
+        let source = ((row + guard) * stride + guard) as usize * 4;
+        let y = (size[1] - 1 - origin[1] - row) as usize;
+        let destination = (y * size[0] as usize + origin[0] as usize) * 4;
+        poster[destination..destination + width]
+            .copy_from_slice(&pixels[source..source + width])
+    }
+}
+
+// ------------------------------------------------------------
+
+// Renders a single frame in tiles no larger than the GPU allows and stitches
+// them into an image, PNG or TIFF by extension, next to the shader by default
+pub fn run(event_loop: &EventLoop<()>, options: PosterOptions) -> anyhow::Result<()>
+{
+    let code = AnnotatedGLSL::new(&fs::read_to_string(&options.path)?)?.into_tiled()?;
+    let window = init_window(event_loop, Some(code.version()))?;
+    let pointers = window.pointers();
+    let root = options.path.parent().unwrap_or(Path::new("."));
+    let setup = code.runtime_setup(root, &mut TextureCache::new(pointers))?;
+    let size = options.size.unwrap_or(setup.resolution);
+    // Lanczos downsampling reaches two window pixels past each pixel, tiles
+    // render that much more on every side so that their edges match
+    let guard = match setup.presentation.antialiasing.downsampling
+    {
+        Downsampling::Lanczos => 2,
+        Downsampling::Box => 0
+    };
+    let limit = [MAX_TEXTURE_SIZE, MAX_RENDERBUFFER_SIZE].into_iter()
+        .map(|name| pointers.get_integer(name) as u32)
+        .min()
+        .unwrap_or_default()
+        / setup.presentation.antialiasing.supersampling;
+    let tile = size.map(|size| size.min(options.tile).min(limit.saturating_sub(2 * guard)).max(1));
+    let mut player = CanvasPlayer::<f32>::new
+    (
+        pointers,
+        setup.timeline,
+        code.code(),
+        setup.textures,
+        tile.map(|size| size + 2 * guard),
+        false,
+        &setup.geometry,
+        None,
+        false,
+        setup.presentation
+    ).map_err(|error| anyhow::anyhow!(setup.source_map.translate(&error.to_string())))?;
+    let mut poster = vec![0; size[0] as usize * size[1] as usize * 4];
+    let origins = tiles(size, tile);
+    // Tiles are read back two calls late, while the next ones render
+    let mut rendered = origins.iter().enumerate();
+    let mut stitch = |pixels: Vec<u8>|
+    {
+        let (index, &origin) = rendered.next().unwrap();
+        place(&mut poster, size, &pixels, tile, origin, guard);
+        logger::info(format!("Rendered tile {} of {}", index + 1, origins.len()))
+    };
+    for &origin in &origins
+    {
+        let origin = origin.map(|offset| offset as i32 - guard as i32);
+        if let Some(pixels) = player.render_tile(Tile{origin, canvas: size}, options.frame)?
+        {
+            stitch(pixels)
+        }
+    }
+    for pixels in player.finish_tiles()?
+    {
+        stitch(pixels)
+    }
+    let output = options.output.unwrap_or_else(|| options.path.with_extension("png"));
+    image::RgbaImage::from_raw(size[0], size[1], poster)
+        .ok_or_else(|| anyhow::anyhow!("Could not create a {}x{} image", size[0], size[1]))?
+        .save(&output)?;
+    logger::info(format!("Saved {}", output.display()));
+    Ok(())
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn options() -> ()
+{
+    let args = |line: &'static str| line.split_whitespace().map(String::from);
+    let options = PosterOptions::parse
+        (args("shader.frag --size 16384 16384 --tile 4096 --frame 30 --output out.tiff"))
+        .unwrap();
+    assert_eq!(options.path, PathBuf::from("shader.frag"));
+    assert_eq!((options.size, options.tile, options.frame), (Some([16384; 2]), 4096, 30));
+    assert_eq!(options.output, Some(PathBuf::from("out.tiff")));
+    let options = PosterOptions::parse(args("shader.frag")).unwrap();
+    assert_eq!((options.size, options.tile, options.frame), (None, 2048, 0));
+    for invalid in ["", "shader.frag --size 0 10", "shader.frag --tile 0", "a --quiet"]
+    {
+        PosterOptions::parse(args(invalid)).map(|_| ()).unwrap_err();
+    }
+}
+
+#[test]
+fn stitch() -> ()
+{
+    let size = [3, 3];
+    let origins = tiles(size, [2, 2]);
+    assert_eq!(origins, [[0, 0], [2, 0], [0, 2], [2, 2]]);
+    let mut poster = vec![0; 36];
+    for (index, &origin) in origins.iter().enumerate()
+    {
+        // Tile pixels hold their row from the bottom and the tile index
+        let pixels: Vec<u8> = (0..16).map(|byte| (byte / 8) * 10 + index as u8).collect();
+        place(&mut poster, size, &pixels, [2, 2], origin, 0)
+    }
+    let rows: Vec<Vec<u8>> = poster.chunks(12)
+        .map(|row| row.chunks(4).map(|pixel| pixel[0]).collect())
+        .collect();
+    assert_eq!(rows, [[2, 2, 3], [10, 10, 11], [0, 0, 1]]);
+    // A 2x2 tile with a one pixel guard band, pixels hold their row and column
+    let pixels: Vec<u8> = (0..64).map(|byte| (byte / 16) * 10 + byte / 4 % 4).collect();
+    let mut poster = vec![0; 16];
+    place(&mut poster, [2, 2], &pixels, [2, 2], [0, 0], 1);
+    let rows: Vec<Vec<u8>> = poster.chunks(8)
+        .map(|row| row.chunks(4).map(|pixel| pixel[0]).collect())
+        .collect();
+    assert_eq!(rows, [[21, 22], [11, 12]])
+}
@@ -0,0 +1,735 @@
+use std::{collections::HashMap, ops::Range};
+use super::lexer::*;
+
+// ------------------------------------------------------------
+
+pub struct Define
+{
+    pub value: String,
+    pub span: Span
+}
+
+pub struct SamplerDeclaration
+{
+    pub name: String,
+    pub path: Option<(String, Span)>
+}
+
+// Annotations of the code that survives the preprocessor conditionals, with
+// the source ranges that are not GLSL and have to be blanked before compiling
+#[derive(Default)]
+pub struct Preprocessed
+{
+    defines: HashMap<String, Define>,
+    pub samplers: Vec<SamplerDeclaration>,
+    pub mesh: Option<(String, Span)>,
+    blanked: Vec<Range<usize>>
+}
+
+impl Preprocessed
+{
+    // The last active definition
+    pub fn define(&self, name: &str) -> Option<&Define>
+    {
+        self.defines.get(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str>
+    {
+        self.define(name).map(|define| define.value.as_str())
+    }
+
+    // Newlines are kept so that lines still match the source
+    pub fn blank(&self, source: &str) -> String
+    {
+        let mut code = String::with_capacity(source.len());
+        let mut at = 0;
+        for range in &self.blanked
+        {
+            code += &source[at..range.start];
+            code.extend
+            (
+                source[range.clone()].chars()
+                    .map(|character| match character
+                    {
+                        '\n' => '\n',
+                        _ => ' '
+                    })
+            );
+            at = range.end
+        }
+        code + &source[at..]
+    }
+}
+
+// ------------------------------------------------------------
+
+// Tokens of `#if` expressions, evaluated as in the C preprocessor. Conditions on
+// the stage macros or on the driver's built-in ones, e.g. `__VERSION__` or
+// `GL_ES`, are `None` as they are only known when compiling
+struct Condition<'a>
+{
+    source: &'a str,
+    tokens: Vec<Token>,
+    next: usize,
+    defines: &'a HashMap<String, Define>,
+    stages: &'a [&'a str],
+    span: Span,
+    // Macros expanding into other macros, bounded against recursive ones
+    depth: usize
+}
+
+impl<'a> Condition<'a>
+{
+    fn peek(&self) -> Option<&'a str>
+    {
+        let source = self.source;
+        self.tokens.get(self.next).map(|token| token.text(source))
+    }
+
+    fn accept(&mut self, text: &str) -> bool
+    {
+        let accepted = self.peek() == Some(text);
+        if accepted
+        {
+            self.next += 1
+        }
+        accepted
+    }
+
+    fn error(&self, message: &str) -> SyntaxError
+    {
+        let span = self.tokens.get(self.next).map_or(self.span, |token| token.span);
+        SyntaxError::new(span, message)
+    }
+
+    fn evaluate(mut self) -> SyntaxResult<Option<bool>>
+    {
+        Ok(self.value()?.map(|value| value != 0))
+    }
+
+    fn value(&mut self) -> SyntaxResult<Option<i64>>
+    {
+        let value = self.ternary()?;
+        match self.peek()
+        {
+            None => Ok(value),
+            Some(_) => Err(self.error("Unexpected token in condition"))
+        }
+    }
+
+    // Binary operators by increasing precedence
+    const OPERATORS: [&'static [&'static str]; 10] =
+    [
+        &["||"],
+        &["&&"],
+        &["|"],
+        &["^"],
+        &["&"],
+        &["==", "!="],
+        &["<=", ">=", "<", ">"],
+        &["<<", ">>"],
+        &["+", "-"],
+        &["*", "/", "%"]
+    ];
+
+    // The longest operator at the next token, punctuation is a token per
+    // character so two adjacent ones may form one operator
+    fn operator(&self) -> Option<&'static str>
+    {
+        let source = self.source;
+        let [first, second] = [0, 1].map
+        (
+            |index| self.tokens.get(self.next + index).map(|token| (token.text(source), token.span))
+        );
+        let operators = Self::OPERATORS.iter().flat_map(|level| level.iter());
+        let pair = match (first, second)
+        {
+            (Some((a, x)), Some((b, y))) if x.end == y.start => format!("{a}{b}"),
+            _ => String::new()
+        };
+        operators.clone().find(|operator| **operator == pair)
+            .or_else(|| operators.clone().find(|operator| Some(**operator) == first.map(|(a, _)| a)))
+            .copied()
+    }
+
+    fn ternary(&mut self) -> SyntaxResult<Option<i64>>
+    {
+        let condition = self.binary(0)?;
+        if !self.accept("?")
+        {
+            return Ok(condition)
+        }
+        let then = self.ternary()?;
+        if !self.accept(":")
+        {
+            return Err(self.error("Expected `:`"))
+        }
+        let otherwise = self.ternary()?;
+        Ok(match condition
+        {
+            Some(0) => otherwise,
+            Some(_) => then,
+            None if then == otherwise => then,
+            None => None
+        })
+    }
+
+    fn binary(&mut self, level: usize) -> SyntaxResult<Option<i64>>
+    {
+        if level == Self::OPERATORS.len()
+        {
+            return self.unary()
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(operator) = self.operator().filter(|operator| Self::OPERATORS[level].contains(operator))
+        {
+            let at = self.tokens[self.next].span;
+            self.next += operator.len();
+            let right = self.binary(level + 1)?;
+            let truth = |value: Option<i64>| value.map(|value| value != 0);
+            left = match operator
+            {
+                "||" => match (truth(left), truth(right))
+                {
+                    (Some(true), _) | (_, Some(true)) => Some(1),
+                    (Some(false), Some(false)) => Some(0),
+                    _ => None
+                },
+                "&&" => match (truth(left), truth(right))
+                {
+                    (Some(false), _) | (_, Some(false)) => Some(0),
+                    (Some(true), Some(true)) => Some(1),
+                    _ => None
+                },
+                "/" | "%" if right == Some(0) =>
+                    return Err(SyntaxError::new(at, "Division by zero in condition")),
+                _ => left.zip(right).map
+                (
+                    |(left, right)| match operator
+                    {
+                        "|" => left | right,
+                        "^" => left ^ right,
+                        "&" => left & right,
+                        "==" => (left == right) as i64,
+                        "!=" => (left != right) as i64,
+                        "<=" => (left <= right) as i64,
+                        ">=" => (left >= right) as i64,
+                        "<" => (left < right) as i64,
+                        ">" => (left > right) as i64,
+                        "<<" => left.wrapping_shl(right as u32),
+                        ">>" => left.wrapping_shr(right as u32),
+                        "+" => left.wrapping_add(right),
+                        "-" => left.wrapping_sub(right),
+                        "*" => left.wrapping_mul(right),
+                        "/" => left.wrapping_div(right),
+                        _ => left.wrapping_rem(right)
+                    }
+                )
+            }
+        }
+        Ok(left)
+    }
+
+    fn unknown(&self, name: &str) -> bool
+    {
+        self.stages.contains(&name) || name.starts_with("__") || name.starts_with("GL_")
+    }
+
+    // Decimal, octal or hexadecimal, GLSL only has the unsigned suffix
+    // but C's long ones are accepted too
+    fn integer(text: &str) -> Option<i64>
+    {
+        let digits = text.trim_end_matches(['u', 'U', 'l', 'L']);
+        let parsed = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X"))
+        {
+            Some(hexadecimal) => u64::from_str_radix(hexadecimal, 16),
+            None if digits.len() > 1 && digits.starts_with('0') => u64::from_str_radix(&digits[1..], 8),
+            None => digits.parse()
+        };
+        parsed.ok().map(|value| value as i64)
+    }
+
+    // Macros that are not integer expressions could expand to anything
+    fn expand(&self, define: &'a Define) -> Option<i64>
+    {
+        if self.depth == 32
+        {
+            return None
+        }
+        let tokens = tokenize(&define.value).ok()?.into_iter()
+            .filter(|token| token.kind != TokenKind::Comment)
+            .collect();
+        Condition
+        {
+            source: &define.value,
+            tokens,
+            next: 0,
+            defines: self.defines,
+            stages: self.stages,
+            span: define.span,
+            depth: self.depth + 1
+        }.value().ok().flatten()
+    }
+
+    fn unary(&mut self) -> SyntaxResult<Option<i64>>
+    {
+        for operator in ["!", "~", "-", "+"]
+        {
+            if self.accept(operator)
+            {
+                return Ok(self.unary()?.map(|value| match operator
+                {
+                    "!" => (value == 0) as i64,
+                    "~" => !value,
+                    "-" => value.wrapping_neg(),
+                    _ => value
+                }))
+            }
+        }
+        if self.accept("(")
+        {
+            let value = self.ternary()?;
+            return match self.accept(")")
+            {
+                true => Ok(value),
+                false => Err(self.error("Expected `)`"))
+            }
+        }
+        let token = match self.tokens.get(self.next)
+        {
+            Some(token) => *token,
+            None => return Err(self.error("Expected a value"))
+        };
+        self.next += 1;
+        let text = token.text(self.source);
+        match token.kind
+        {
+            TokenKind::Number => Self::integer(text).map(Some)
+                .ok_or_else(|| SyntaxError::new(token.span, format!("Expected an integer, found `{text}`"))),
+            TokenKind::Identifier if text == "defined" =>
+            {
+                let parenthesized = self.accept("(");
+                let name = self.peek().ok_or_else(|| self.error("Expected a macro name"))?;
+                self.next += 1;
+                if parenthesized && !self.accept(")")
+                {
+                    return Err(self.error("Expected `)`"))
+                }
+                match self.unknown(name)
+                {
+                    true => Ok(None),
+                    false => Ok(Some(self.defines.contains_key(name) as i64))
+                }
+            }
+            TokenKind::Identifier => match self.defines.get(text)
+            {
+                _ if self.unknown(text) => Ok(None),
+                Some(define) => Ok(self.expand(define)),
+                None => Ok(Some(0))
+            },
+            _ => Err(SyntaxError::new(token.span, format!("Unexpected `{text}` in condition")))
+        }
+    }
+}
+
+// ------------------------------------------------------------
+
+struct Branch
+{
+    // Whether the enclosing code is active
+    parent: bool,
+    active: bool,
+    // Whether a branch was certainly taken already
+    taken: bool,
+    span: Span
+}
+
+fn branch
+(
+    stack: &mut Vec<Branch>,
+    directive: &str,
+    condition: impl FnOnce() -> SyntaxResult<Option<bool>>,
+    span: Span
+) -> SyntaxResult<()>
+{
+    let active = stack.last().is_none_or(|branch| branch.active);
+    match directive
+    {
+        "if" | "ifdef" | "ifndef" =>
+        {
+            let condition = match active
+            {
+                true => condition()?,
+                false => Some(false)
+            };
+            stack.push
+            (
+                Branch
+                {
+                    parent: active,
+                    active: active && condition != Some(false),
+                    taken: condition == Some(true),
+                    span
+                }
+            )
+        }
+        "elif" | "else" =>
+        {
+            let branch = match stack.last_mut()
+            {
+                Some(branch) => branch,
+                None => return Err(SyntaxError::new(span, format!("`#{directive}` without `#if`")))
+            };
+            let condition = match (branch.parent && !branch.taken, directive)
+            {
+                (false, _) => Some(false),
+                (true, "else") => Some(true),
+                (true, _) => condition()?
+            };
+            branch.active = branch.parent && !branch.taken && condition != Some(false);
+            branch.taken |= condition == Some(true)
+        }
+        _ =>
+        {
+            if stack.pop().is_none()
+            {
+                return Err(SyntaxError::new(span, "`#endif` without `#if`"))
+            }
+        }
+    }
+    Ok(())
+}
+
+// ------------------------------------------------------------
+
+// Value of an annotation, quoted or the raw text without comments
+fn annotation_value(source: &str, tokens: &[Token]) -> String
+{
+    let uncommented: Vec<&Token> = tokens.iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    match uncommented[..]
+    {
+        [token] if token.kind == TokenKind::String =>
+        {
+            let text = token.text(source);
+            text[1..text.len() - 1].to_string()
+        }
+        _ => text_between(source, tokens)
+    }
+}
+
+// Whether a statement declares a sampler named by its last token,
+// e.g. `layout(binding = 0) uniform lowp sampler2D name`
+fn sampler_name<'a>(source: &'a str, statement: &[Token]) -> Option<&'a str>
+{
+    let (name, qualifiers) = statement.split_last()?;
+    let mut depth = 0;
+    let mut uniform = false;
+    let mut sampler = false;
+    for token in qualifiers
+    {
+        match token.text(source)
+        {
+            "(" => depth += 1,
+            ")" => depth -= 1,
+            "uniform" if depth == 0 => uniform = true,
+            "sampler2D" if depth == 0 => sampler = uniform,
+            _ => {}
+        }
+    }
+    match name.kind == TokenKind::Identifier && sampler
+    {
+        true => Some(name.text(source)),
+        false => None
+    }
+}
+
+// Stage macros are never defined in the source, they are only known once each
+// stage is compiled
+pub fn preprocess(source: &str, stages: &[&str]) -> SyntaxResult<Preprocessed>
+{
+    let tokens = tokenize(source)?;
+    let mut preprocessed = Preprocessed::default();
+    let mut stack: Vec<Branch> = vec!();
+    let mut statement: Vec<Token> = vec!();
+    let mut annotation: Option<(Token, Vec<Token>)> = None;
+    for token in tokens
+    {
+        let active = stack.last().is_none_or(|branch| branch.active);
+        if token.kind == TokenKind::Directive
+        {
+            let body = tokenize_directive(source, &token)?;
+            let (name, arguments) = match body.split_first()
+            {
+                Some((name, arguments)) if name.kind == TokenKind::Identifier =>
+                    (name.text(source), arguments),
+                _ => continue
+            };
+            let arguments: Vec<Token> = arguments.iter()
+                .filter(|token| token.kind != TokenKind::Comment)
+                .copied()
+                .collect();
+            let condition = ||
+            {
+                let macro_name = || match arguments.first()
+                {
+                    Some(argument) if argument.kind == TokenKind::Identifier =>
+                        Ok(argument.text(source)),
+                    _ => Err(SyntaxError::new(token.span, format!("Expected a macro name after `#{name}`")))
+                };
+                let defined = |name: &str| match stages.contains(&name) || name.starts_with("GL_") || name.starts_with("__")
+                {
+                    true => None,
+                    false => Some(preprocessed.defines.contains_key(name))
+                };
+                match name
+                {
+                    "ifdef" => Ok(defined(macro_name()?)),
+                    "ifndef" => Ok(defined(macro_name()?).map(|defined| !defined)),
+                    _ => Condition
+                    {
+                        source,
+                        tokens: arguments.clone(),
+                        next: 0,
+                        defines: &preprocessed.defines,
+                        stages,
+                        span: token.span,
+                        depth: 0
+                    }.evaluate()
+                }
+            };
+            match name
+            {
+                "if" | "ifdef" | "ifndef" | "elif" | "else" | "endif" =>
+                    branch(&mut stack, name, condition, token.span)?,
+                _ if !active => {}
+                "define" => match arguments.first()
+                {
+                    Some(macro_name) if macro_name.kind == TokenKind::Identifier =>
+                    {
+                        let value = body.iter()
+                            .position(|token| token == macro_name)
+                            .map_or(&[][..], |index| &body[index + 1..]);
+                        let define = Define
+                        {
+                            value: text_between(source, value),
+                            span: token.span
+                        };
+                        preprocessed.defines.insert(macro_name.text(source).to_string(), define);
+                    }
+                    _ => return Err(SyntaxError::new(token.span, "Expected a macro name after `#define`"))
+                },
+                "undef" =>
+                {
+                    if let Some(macro_name) = arguments.first()
+                    {
+                        preprocessed.defines.remove(macro_name.text(source));
+                    }
+                }
+                "mesh" =>
+                {
+                    let path = annotation_value(source, &body[1..]);
+                    if path.is_empty()
+                    {
+                        return Err(SyntaxError::new(token.span, "Expected a path for the 'mesh' directive"))
+                    }
+                    preprocessed.mesh = Some((path, token.span));
+                    preprocessed.blanked.push(token.span.range())
+                }
+                _ => {}
+            }
+            continue
+        }
+        if !active
+        {
+            continue
+        }
+        if let Some((at, value)) = &mut annotation
+        {
+            match token.is(source, ";")
+            {
+                false => value.push(token),
+                true =>
+                {
+                    let path = annotation_value(source, value);
+                    if path.is_empty()
+                    {
+                        return Err(SyntaxError::new(at.span, "Expected a texture path after `@`"))
+                    }
+                    let name = sampler_name(source, &statement).unwrap_or_default().to_string();
+                    preprocessed.samplers.push(SamplerDeclaration{name, path: Some((path, at.span))});
+                    preprocessed.blanked.push(at.span.start..token.span.start);
+                    annotation = None;
+                    statement.clear()
+                }
+            }
+            continue
+        }
+        match token.text(source)
+        {
+            _ if token.kind == TokenKind::Comment => {}
+            "@" => match sampler_name(source, &statement)
+            {
+                Some(_) => annotation = Some((token, vec!())),
+                None => return Err
+                (
+                    SyntaxError::new(token.span, "`@` only annotates `uniform sampler2D` declarations")
+                )
+            },
+            ";" | "{" | "}" =>
+            {
+                if let Some(name) = sampler_name(source, &statement)
+                {
+                    let name = name.to_string();
+                    preprocessed.samplers.push(SamplerDeclaration{name, path: None})
+                }
+                statement.clear()
+            }
+            _ => statement.push(token)
+        }
+    }
+    if let Some((at, _)) = annotation
+    {
+        return Err(SyntaxError::new(at.span, "Expected `;` after the texture path"))
+    }
+    if let Some(branch) = stack.last()
+    {
+        return Err(SyntaxError::new(branch.span, "Unterminated conditional"))
+    }
+    Ok(preprocessed)
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn conditionals() -> ()
+{
+    let source = "
+        #define A 2
+        #if A > 1 && !defined(B)
+            #define taken
+        #elif 1
+            #define not_taken
+        #endif
+        #ifdef B
+            #define b
+        #else
+            #define not_b
+        #endif
+        #if 0
+            #if 1
+                #define nested
+            #endif
+        #endif
+        #ifdef VERTEX
+            #define vertex
+        #else
+            #define fragment
+        #endif
+        #undef A
+        #if A
+            #define a
+        #endif
+    ";
+    let preprocessed = preprocess(source, &["VERTEX"]).unwrap();
+    for (name, defined) in
+    [
+        ("taken", true),
+        ("not_taken", false),
+        ("b", false),
+        ("not_b", true),
+        ("nested", false),
+        ("vertex", true),
+        ("fragment", true),
+        ("a", false)
+    ]
+    {
+        assert_eq!(preprocessed.define(name).is_some(), defined, "{name}")
+    }
+    for (invalid, line) in
+    [
+        ("#if 1\n", 1),
+        ("\n#endif", 2),
+        ("#else", 1),
+        ("\n\n#if (1", 3),
+        ("#if 1 +", 1),
+        ("\n#if 1 / (2 - 2)", 2),
+        ("#if 1 ? 2", 1),
+        ("#if 1.5", 1)
+    ]
+    {
+        assert_eq!(preprocess(invalid, &[]).err().unwrap().span.line, line, "{invalid}")
+    }
+}
+
+#[test]
+fn expressions() -> ()
+{
+    let taken = |condition: &str|
+    {
+        let source = format!("#define N 3\n#define M (N * 2)\n#define R R\n#if {condition}\n#define taken\n#endif");
+        preprocess(&source, &["VERTEX"]).unwrap().define("taken").is_some()
+    };
+    for (condition, expected) in
+    [
+        ("N * 2 > 4", true),
+        ("N + N - 7", true),
+        ("M == 6 && M % 4 == 2", true),
+        ("-1", true),
+        ("-1 < 0 && ~0 == -1 && +2 == 2", true),
+        ("0x10 == 16 && 0X1f == 31 && 010 == 8 && 4u == 4", true),
+        ("(N & 1) && (N | 4) == 7 && (N ^ 1) == 2", true),
+        ("1 << 4 == 16 && 256 >> N == 32", true),
+        ("N > 2 ? N - 3 : 1", false),
+        ("7 / 2 == 3 && -7 / 2 == -3", true),
+        ("2 + 3 * 4 == 14 && (2 + 3) * 4 == 20", true),
+        ("N<=3 && N>=3 && N!=4 && !(N<3)", true),
+        // Recursive macros could expand to anything, as could the stages
+        ("R == 0", true),
+        ("VERTEX * 0", true)
+    ]
+    {
+        assert_eq!(taken(condition), expected, "{condition}")
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn annotations() -> ()
+{
+    let source = "#define size 1 /* two */ 2\n\
+        layout(binding = 1) uniform\n  sampler2D a @ \"a b.png\";\n\
+        uniform sampler2D b;\n\
+        #mesh  bunny.obj // model\n\
+        uniform sampler2D c @ c/**/.png\n;";
+    let preprocessed = preprocess(source, &[]).unwrap();
+    assert_eq!(preprocessed.value("size"), Some("1  2"));
+    let samplers: Vec<(&str, Option<&str>)> = preprocessed.samplers.iter()
+        .map(|sampler| (sampler.name.as_str(), sampler.path.as_ref().map(|(path, _)| path.as_str())))
+        .collect();
+    assert_eq!(samplers, [("a", Some("a b.png")), ("b", None), ("c", Some("c.png"))]);
+    let (path, span) = preprocessed.mesh.as_ref().unwrap();
+    assert_eq!((path.as_str(), span.line), ("bunny.obj", 5));
+    let blanked = preprocessed.blank(source);
+    let blanked: Vec<&str> = blanked.lines().map(str::trim_end).collect();
+    assert_eq!
+    (
+        blanked,
+        [
+            "#define size 1 /* two */ 2",
+            "layout(binding = 1) uniform",
+            &format!("  sampler2D a {};", " ".repeat(11)),
+            "uniform sampler2D b;",
+            "",
+            "uniform sampler2D c",
+            ";"
+        ]
+    );
+    let error = preprocess("void main()\n{\n    float x = 1.0 @ 2;\n}", &[]).err().unwrap();
+    assert_eq!((error.span.line, error.span.column), (3, 19));
+    let error = preprocess("uniform sampler2D a @ a.png", &[]).err().unwrap();
+    assert_eq!((error.span.line, error.span.column), (1, 21))
+}
@@ -1,5 +1,5 @@
 
-use super::{canvas::*, timeline::*};
+use super::{canvas::*, timeline::*, sourcemap::*, logger};
 
 // ------------------------------------------------------------
 
@@ -7,8 +7,13 @@ pub struct RuntimeSetup
 {
     pub resolution: [u32; 2],
     pub feedback: bool,
+    pub geometry: Geometry,
+    pub compute: Option<Compute>,
+    pub hud: bool,
+    pub presentation: Presentation,
     pub timeline: Timeline,
-    pub textures: Vec<NamedTexture>
+    pub textures: Vec<NamedTexture>,
+    pub source_map: SourceMap
 }
 
 // ------------------------------------------------------------
@@ -26,7 +31,11 @@ impl<D> RuntimeErrored<D>
         D: ogl::TextureComponentDataType,
         E: std::fmt::Display
     {
-        eprintln!("\n{error}\n");
+        let message = error.to_string();
+        if !message.is_empty()
+        {
+            logger::error(message)
+        }
         let inner = CanvasPlayer::new
         (
             pointers,
@@ -34,7 +43,11 @@ impl<D> RuntimeErrored<D>
             super::shaders::ERROR_SHADER,
             vec!(),
             [500; 2],
-            false
+            false,
+            &Geometry::Quad,
+            None,
+            false,
+            Presentation::default()
         )?;
         Ok(Self(inner))
     }
@@ -44,15 +57,25 @@ impl<D> RuntimeErrored<D>
         self.0.pointers()
     }
 
-    fn refresh(&mut self) -> bool
+    fn refresh(&mut self) -> ogl::OGLResult<bool>
     {
         self.0.refresh()
     }
 
+    fn notify(&mut self, message: &str) -> ()
+    {
+        self.0.notify(message)
+    }
+
     fn resolution(&self) -> [u32; 2]
     {
         self.0.resolution()
     }
+
+    fn alpha(&mut self) -> ogl::OGLResult<Option<Vec<u8>>>
+    {
+        self.0.alpha()
+    }
 }
 
 // ------------------------------------------------------------
@@ -104,14 +127,31 @@ impl<D> RuntimeState<D>
             code,
             setup.textures,
             setup.resolution,
-            setup.feedback
+            setup.feedback,
+            &setup.geometry,
+            setup.compute.as_ref(),
+            setup.hud,
+            setup.presentation
         )
         {
-            Ok(canvas) => Ok(*self = canvas.into()),
-            Err(error) => self.into_errored(&error)
+            Ok(canvas) =>
+            {
+                for warning in canvas.warnings()
+                {
+                    logger::warning(warning)
+                }
+                Ok(*self = canvas.into())
+            }
+            Err(error) if error.is_context_lost() => Err(error),
+            Err(error) => self.into_errored(&setup.source_map.translate(&error.to_string()))
         }
     }
 
+    pub fn is_errored(&self) -> bool
+    {
+        matches!(self, Self::Errored(_))
+    }
+
     pub fn pointers(&self) -> &ogl::FunctionPointers
     {
         match self
@@ -121,6 +161,15 @@ impl<D> RuntimeState<D>
         }
     }
 
+    pub fn notify(&mut self, message: &str) -> ()
+    {
+        match self
+        {
+            Self::Running(running) => running.notify(message),
+            Self::Errored(errored) => errored.notify(message)
+        }
+    }
+
     pub fn into_errored<E>(&mut self, error: &E) -> ogl::OGLResult<()>
     where
         D: ogl::TextureComponentDataType,
@@ -130,12 +179,19 @@ impl<D> RuntimeState<D>
             .map(|e| *self = e.into())
     }
 
-    pub fn refresh(&mut self) -> bool
+    pub fn refresh(&mut self) -> ogl::OGLResult<bool>
+    where D: ogl::TextureComponentDataType
     {
-        match self
+        let result = match self
         {
             Self::Running(running) => running.refresh(),
-            Self::Errored(errored) => errored.refresh()
+            Self::Errored(errored) => return errored.refresh()
+        };
+        match result
+        {
+            Ok(refreshed) => Ok(refreshed),
+            Err(error) if error.is_context_lost() => Err(error),
+            Err(error) => self.into_errored(&error).map(|_| false)
         }
     }
     
@@ -147,5 +203,14 @@ impl<D> RuntimeState<D>
             Self::Errored(errored) => errored.resolution()
         }
     }
+
+    pub fn alpha(&mut self) -> ogl::OGLResult<Option<Vec<u8>>>
+    {
+        match self
+        {
+            Self::Running(running) => running.alpha(),
+            Self::Errored(errored) => errored.alpha()
+        }
+    }
 }
 
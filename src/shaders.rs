@@ -1,12 +1,158 @@
+use std::fmt;
+
+// ------------------------------------------------------------
+
+// Version from a #version directive, sources without one are GLSL 1.10
+// and `#version 100` is the only one of GLSL ES 1.00
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GLSLVersion
+{
+    pub number: u32,
+    pub es: bool
+}
+
+impl Default for GLSLVersion
+{
+    fn default() -> Self
+    {
+        Self{number: 110, es: false}
+    }
+}
+
+impl fmt::Display for GLSLVersion
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+        match self.es
+        {
+            true => write!(formatter, "{} es", self.number),
+            false => write!(formatter, "{}", self.number)
+        }
+    }
+}
+
+impl GLSLVersion
+{
+    // The first #version directive, comments are expected to be stripped
+    pub fn of(code: &str) -> Option<Self>
+    {
+        code.lines().find_map
+        (
+            |line|
+            {
+                let mut tokens = line.trim_start()
+                    .strip_prefix('#')?
+                    .trim_start()
+                    .strip_prefix("version")?
+                    .split_ascii_whitespace();
+                let number = tokens.next()?.parse().ok()?;
+                Some(Self{number, es: number == 100 || tokens.next() == Some("es")})
+            }
+        )
+    }
+
+    fn directive(&self) -> String
+    {
+        match (self.es, self.number >= 150)
+        {
+            (true, _) if self.number == 100 => String::from("#version 100\nprecision highp float;"),
+            (true, _) => format!("#version {} es\nprecision highp float;", self.number),
+            (false, true) => format!("#version {} core", self.number),
+            (false, false) => format!("#version {}", self.number)
+        }
+    }
+
+    // Stage interfaces use attribute and varying before GLSL 1.30 and ES 3.00
+    fn legacy(&self) -> bool
+    {
+        match self.es
+        {
+            true => self.number < 300,
+            false => self.number < 130
+        }
+    }
+
+    // Full screen quad vertex shader that links with fragment shaders of this
+    // version, ES shaders only link with shaders of the same version
+    pub fn quad_vertex_shader(&self) -> String
+    {
+        self.quad_vertex_shader_with("", "corners * 0.5 + 0.5")
+    }
+
+    // Main programs see `st` over the `glis_tile` rectangle of the whole
+    // canvas, given as [x, y, width, height]
+    pub fn tiled_quad_vertex_shader(&self) -> String
+    {
+        self.quad_vertex_shader_with
+            ("uniform vec4 glis_tile;", "glis_tile.xy + (corners * 0.5 + 0.5) * glis_tile.zw")
+    }
+
+    fn quad_vertex_shader_with(&self, declarations: &str, st: &str) -> String
+    {
+        let (input, output) = match self.legacy()
+        {
+            true => ("attribute", "varying"),
+            false => ("in", "out")
+        };
+        format!
+        (
+            "{}
+            {input} vec2 corners;
+            {output} vec2 st;
+            {declarations}
+            void main()
+            {{
+                gl_Position = vec4(corners, 0.0, 1.0);
+                st = {st};
+            }}
+            ",
+            self.directive()
+        )
+    }
+
+    // Fragment shader passing through the color of a vertex shader of this version
+    pub fn color_fragment_shader(&self) -> String
+    {
+        match self.legacy()
+        {
+            true => format!
+            (
+                "{}
+                varying vec4 v_color;
+                void main()
+                {{
+                    gl_FragColor = v_color;
+                }}
+                ",
+                self.directive()
+            ),
+            false => format!
+            (
+                "{}
+                in vec4 v_color;
+                out vec4 color;
+                void main()
+                {{
+                    color = v_color;
+                }}
+                ",
+                self.directive()
+            )
+        }
+    }
+}
+
+// ------------------------------------------------------------
 
 pub const ERROR_SHADER: &'static str =
 "
-#version 120
+#version 330 core
 in vec2 st;
+out vec4 color;
 uniform float time;
 void main()
 {
-    gl_FragColor = vec4
+    color = vec4
     (
         cos(st.x * 20.0 + time) + 
         sin(st.y * 20.0 + time),
@@ -15,3 +161,72 @@ void main()
 }
 ";
 
+
+// Draws the glyph grid set up by the HUD, in the top left
+// corner or the bottom left one for notifications
+pub const TEXT_SHADER: &'static str =
+"
+#version 330 core
+in vec2 st;
+out vec4 color;
+uniform int glyphs[384];
+uniform ivec2 grid;
+uniform bool bottom;
+uniform vec2 resolution;
+const ivec2 CELL = ivec2(4, 6);
+const float SCALE = 2.0;
+void main()
+{
+    vec2 position = vec2(st.x, 1.0 - st.y) * resolution / SCALE - 2.0;
+    if (bottom)
+    {
+        position.y -= resolution.y / SCALE - 4.0 - float(grid.y * CELL.y);
+    }
+    if
+    (
+        any(lessThan(position, vec2(-1.0)))
+            || any(greaterThanEqual(position, vec2(grid * CELL)))
+    )
+    {
+        discard;
+    }
+    ivec2 pixel = ivec2(max(position, 0.0));
+    ivec2 cell = pixel / CELL;
+    ivec2 local = pixel - cell * CELL;
+    int bits = glyphs[cell.y * grid.x + cell.x];
+    bool lit = all(greaterThanEqual(position, vec2(0.0)))
+        && local.x < 3 && local.y < 5
+        && ((bits >> (14 - local.y * 3 - local.x)) & 1) == 1;
+    color = lit ? vec4(1.0) : vec4(0.0, 0.0, 0.0, 0.6);
+}
+";
+
+// ------------------------------------------------------------
+
+#[test]
+fn versions() -> ()
+{
+    let version = |code: &str| GLSLVersion::of(code);
+    assert_eq!(version("void main(){}"), None);
+    assert_eq!(version("\n  # version 300 es\n"), Some(GLSLVersion{number: 300, es: true}));
+    assert_eq!(version("#version 330 core"), Some(GLSLVersion{number: 330, es: false}));
+    for (version, lines) in
+    [
+        ("#version 100", ["#version 100", "attribute vec2 corners;"]),
+        ("#version 300 es", ["#version 300 es", "in vec2 corners;"]),
+        ("#version 120", ["#version 120", "attribute vec2 corners;"]),
+        ("#version 130", ["#version 130", "in vec2 corners;"]),
+        ("#version 330", ["#version 330 core", "out vec2 st;"]),
+        ("#version 460 core", ["#version 460 core", "in vec2 corners;"])
+    ]
+    {
+        let version = GLSLVersion::of(version).unwrap();
+        for shader in [version.quad_vertex_shader(), version.tiled_quad_vertex_shader()]
+        {
+            let shader: Vec<&str> = shader.lines().map(str::trim).collect();
+            assert!(lines.iter().all(|line| shader.contains(line)), "{shader:?}")
+        }
+    }
+    let fragment = GLSLVersion{number: 100, es: true}.color_fragment_shader();
+    assert!(fragment.contains("precision highp float;") && fragment.contains("gl_FragColor"))
+}
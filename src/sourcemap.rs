@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+// ------------------------------------------------------------
+
+// Lines of the code handed to the compiler back to the lines of the user's
+// file, `None` for the lines glis injects. Comment stripping and annotation
+// blanking keep lines in place so only injected lines shift them
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap(Vec<Option<usize>>);
+
+impl SourceMap
+{
+    pub fn new(code: &str) -> Self
+    {
+        Self((1..=code.lines().count()).map(Some).collect())
+    }
+
+    // Lines inserted before the 0-based line index of the processed code
+    pub fn inject(&mut self, index: usize, count: usize) -> ()
+    {
+        let index = index.min(self.0.len());
+        self.0.splice(index..index, std::iter::repeat_n(None, count));
+    }
+
+    pub fn original(&self, line: usize) -> Option<usize>
+    {
+        self.0.get(line.checked_sub(1)?).copied().flatten()
+    }
+
+    // Byte range of the line number in a compiler log line, e.g. `0:12(5): error`
+    // from Mesa, `0(12) : error` from NVIDIA or `ERROR: 0:12: ...` from others
+    fn locate(line: &str) -> Option<Range<usize>>
+    {
+        let offset = ["ERROR: ", "WARNING: "].iter()
+            .find_map(|prefix| line.strip_prefix(prefix).map(|_| prefix.len()))
+            .unwrap_or(0);
+        let rest = &line[offset..];
+        let source = rest.find(|character: char| !character.is_ascii_digit())?;
+        if source == 0 || !matches!(rest[source..].chars().next(), Some(':' | '('))
+        {
+            return None
+        }
+        let start = source + 1;
+        let length = rest[start..].find(|character: char| !character.is_ascii_digit())
+            .unwrap_or(rest.len() - start);
+        match length
+        {
+            0 => None,
+            _ => Some(offset + start..offset + start + length)
+        }
+    }
+
+    // Compiler logs with line numbers of the user's file
+    pub fn translate(&self, log: &str) -> String
+    {
+        let lines: Vec<String> = log.lines().map
+        (
+            |line| match Self::locate(line)
+            {
+                Some(range) =>
+                {
+                    let processed: usize = line[range.clone()].parse().unwrap_or(0);
+                    match self.original(processed)
+                    {
+                        Some(original) => format!
+                        (
+                            "{}{original}{}",
+                            &line[..range.start],
+                            &line[range.end..]
+                        ),
+                        None => format!("{line} (in code injected by glis)")
+                    }
+                }
+                None => line.to_string()
+            }
+        ).collect();
+        lines.join("\n")
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn translate() -> ()
+{
+    let mut map = SourceMap::new("a\nb\nc");
+    map.inject(0, 2);
+    map.inject(3, 1);
+    assert_eq!
+    (
+        (1..=7).map(|line| map.original(line)).collect::<Vec<Option<usize>>>(),
+        [None, None, Some(1), None, Some(2), Some(3), None]
+    );
+    let log = "0:5(12): error: `x' undeclared\n\
+        0(6) : error C1008: undefined variable \"y\"\n\
+        ERROR: 0:3: 'z' : undeclared identifier\n\
+        0:1(1): error: redefinition\n\
+        linking failed";
+    assert_eq!
+    (
+        map.translate(log),
+        "0:2(12): error: `x' undeclared\n\
+        0(3) : error C1008: undefined variable \"y\"\n\
+        ERROR: 0:1: 'z' : undeclared identifier\n\
+        0:1(1): error: redefinition (in code injected by glis)\n\
+        linking failed"
+    )
+}
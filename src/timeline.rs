@@ -1,13 +1,14 @@
 
 use std::
 {
-    time::Instant,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
     num::NonZeroU32
 };
+use super::logger;
 
 // ------------------------------------------------------------
 
-pub type Frame = u32;
+pub type Frame = u64;
 
 // ------------------------------------------------------------
 
@@ -41,12 +42,36 @@ impl Default for FrameRange
 
 // ------------------------------------------------------------
 
+// How frames follow the wall clock
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+pub enum Stepping
+{
+    // The frame due by the wall clock, late ones are skipped
+    #[default]
+    RealTime,
+    // Every frame once and no faster than the frame rate
+    Fixed,
+    // The frame due by the wall clock with every late frame rendered before
+    // it so that simulations advance the same everywhere. A limit bounds the
+    // late frames rendered at once, dropping the earlier ones
+    CatchUp(Option<u32>),
+    // Every frame once, as fast as frames are asked for
+    Unpaced
+}
+
+// ------------------------------------------------------------
+
 #[derive(Clone)]
 pub struct Timeline
 {
     fps: u32,
     frame: Frame,
     range: FrameRange,
+    dropped: u32,
+    stepping: Stepping,
+    steps: u64,
+    behind: bool,
+    period: Option<f64>,
     onset: Option<Instant>
 }
 
@@ -67,10 +92,37 @@ impl Timeline
     pub fn new(FPS(fps): FPS, range: FrameRange) -> Self
     {
         let fps = fps.get();
-        Self{fps, frame: 0, range, onset: None}
+        Self
+        {
+            fps,
+            frame: 0,
+            range,
+            dropped: 0,
+            stepping: Stepping::default(),
+            steps: 0,
+            behind: false,
+            period: None,
+            onset: None
+        }
+    }
+
+    // Seconds after which the `time` uniform starts over
+    pub fn with_period(self, period: Option<f64>) -> Self
+    {
+        Self{period, ..self}
+    }
+
+    pub fn with_stepping(self, stepping: Stepping) -> Self
+    {
+        Self{stepping, ..self}
+    }
+
+    // Advances by exactly one frame per call regardless of the wall clock
+    pub fn stepped(self) -> Self
+    {
+        self.with_stepping(Stepping::Unpaced)
     }
 
-    #[allow(unused)]
     pub fn fps(&self) -> u32
     {
         self.fps
@@ -86,9 +138,84 @@ impl Timeline
         None
     }
 
-    pub fn time(&self) -> f32
+    pub fn time(&self) -> f64
+    {
+        self.time_at(self.frame)
+    }
+
+    pub fn time_at(&self, frame: Frame) -> f64
     {
-        self.frame as f32 / self.fps as f32
+        frame as f64 / self.fps as f64
+    }
+
+    // Single precision only lasts hours, wrapping keeps it
+    pub fn wrap_time(&self, time: f64) -> f32
+    {
+        match self.period
+        {
+            Some(period) => time.rem_euclid(period) as f32,
+            None => time as f32
+        }
+    }
+
+    // Frames skipped because a refresh came later than the frame rate
+    pub fn dropped(&self) -> u32
+    {
+        self.dropped
+    }
+
+    // Whether more frames are due when catching up, the last frame
+    // is only rendered to advance the canvas and not shown
+    pub fn behind(&self) -> bool
+    {
+        self.behind
+    }
+
+    fn wrap(&self, steps: u64) -> Frame
+    {
+        match self.range
+        {
+            FrameRange::Bounded(end) => steps % end.get() as Frame,
+            FrameRange::Endless => steps
+        }
+    }
+
+    // Steps towards the frame due by the wall clock, counted since the onset
+    fn step(&mut self, due: u64) -> Option<Frame>
+    {
+        if due <= self.steps
+        {
+            return None
+        }
+        if let Stepping::CatchUp(Some(limit)) = self.stepping
+        {
+            let late = due - self.steps;
+            if late > limit as u64
+            {
+                let dropped = u32::try_from(late - limit as u64).unwrap_or(u32::MAX);
+                if self.dropped == 0
+                {
+                    logger::warning(format!("Catch-up dropped {dropped} late frames past its limit of {limit}"))
+                }
+                self.dropped = self.dropped.saturating_add(dropped);
+                self.steps = due - limit as u64
+            }
+        }
+        self.steps += 1;
+        self.behind = matches!(self.stepping, Stepping::CatchUp(_)) && due > self.steps;
+        self.frame = self.wrap(self.steps);
+        Some(self.frame)
+    }
+
+    fn skipped(previous: Frame, frame: Frame, range: FrameRange) -> u32
+    {
+        let ahead = match (frame > previous, range)
+        {
+            (true, _) => frame - previous,
+            (false, FrameRange::Bounded(end)) => end.get() as Frame - previous + frame,
+            (false, FrameRange::Endless) => 0
+        };
+        u32::try_from(ahead.saturating_sub(1)).unwrap_or(u32::MAX)
     }
 }
 
@@ -97,24 +224,54 @@ impl Iterator for Timeline
     type Item = Frame;
     fn next(&mut self) -> Option<Self::Item>
     {
+        match (self.stepping, self.onset)
+        {
+            (Stepping::RealTime, _) => {}
+            (_, None) =>
+            {
+                self.onset = Some(Instant::now());
+                self.steps = 0;
+                self.frame = 0;
+                return Some(0)
+            }
+            (Stepping::Unpaced, Some(_)) =>
+            {
+                self.steps += 1;
+                self.frame = self.wrap(self.steps);
+                return Some(self.frame)
+            }
+            (stepping, Some(onset)) =>
+            {
+                let due = (self.fps as f64 * onset.elapsed().as_secs_f64()) as u64;
+                let frame = self.step(due);
+                // Fixed steps after a stall resume at the frame rate
+                if stepping == Stepping::Fixed && due > self.steps
+                {
+                    let behind = Duration::from_secs_f64(self.steps as f64 / self.fps as f64);
+                    self.onset = Instant::now().checked_sub(behind).or(self.onset)
+                }
+                return frame
+            }
+        }
         match &mut self.onset
         {
             Some(ref mut onset) =>
             {
                 let mut frame: Frame =
                 (
-                    self.fps as f32 * onset.elapsed()
-                        .as_secs_f32()
+                    self.fps as f64 * onset.elapsed()
+                        .as_secs_f64()
                 ) as _;
                 if let FrameRange::Bounded(end) = self.range
                 {
-                    frame %= end.get()
+                    frame %= end.get() as Frame
                 }
                 match frame == self.frame
                 {
                     true => None,
                     false =>
                     {
+                        self.dropped += Self::skipped(self.frame, frame, self.range);
                         self.frame = frame;
                         if frame == 0
                         {
@@ -133,3 +290,140 @@ impl Iterator for Timeline
     }
 }
 
+
+// ------------------------------------------------------------
+
+#[test]
+fn dropped() -> ()
+{
+    let range = FrameRange::Bounded(NonZeroU32::new(100).unwrap());
+    assert_eq!(Timeline::skipped(10, 11, range), 0);
+    assert_eq!(Timeline::skipped(10, 14, range), 3);
+    assert_eq!(Timeline::skipped(98, 1, range), 2);
+    assert_eq!(Timeline::skipped(98, 1, FrameRange::Endless), 0);
+    assert_eq!(Timeline::default().dropped(), 0);
+    let timeline = Timeline::new(FPS::default(), range).stepped();
+    assert_eq!(timeline.take(102).last(), Some(1))
+}
+
+#[test]
+fn stepping() -> ()
+{
+    let range = FrameRange::Bounded(NonZeroU32::new(8).unwrap());
+    let mut fixed = Timeline::new(FPS::default(), range).with_stepping(Stepping::Fixed);
+    assert_eq!(fixed.step(0), None);
+    assert_eq!(fixed.step(5), Some(1));
+    assert_eq!((fixed.dropped(), fixed.behind()), (0, false));
+    let mut catch_up = Timeline::new(FPS::default(), range).with_stepping(Stepping::CatchUp(Some(2)));
+    assert_eq!(catch_up.step(2), Some(1));
+    assert!(catch_up.behind());
+    assert_eq!(catch_up.step(2), Some(2));
+    assert!(!catch_up.behind());
+    assert_eq!(catch_up.step(2), None);
+    // Only the last two late frames are rendered and the frames wrap
+    assert_eq!(catch_up.step(10), Some(1));
+    assert_eq!((catch_up.dropped(), catch_up.behind()), (6, true));
+    assert_eq!(catch_up.step(10), Some(2));
+    assert!(!catch_up.behind());
+    // Without a limit every late frame is rendered
+    let mut unbounded = Timeline::new(FPS::default(), range).with_stepping(Stepping::CatchUp(None));
+    let frames: Vec<Frame> = std::iter::from_fn(|| unbounded.step(10)).collect();
+    assert_eq!(frames, [1, 2, 3, 4, 5, 6, 7, 0, 1, 2]);
+    assert_eq!(unbounded.dropped(), 0)
+}
+
+// Error-free split of a time into floats, `hi + lo` keeps double precision
+// for shaders that need the unwrapped time
+pub fn split_time(time: f64) -> [f32; 2]
+{
+    let hi = time as f32;
+    [hi, (time - hi as f64) as f32]
+}
+
+#[test]
+fn precision() -> ()
+{
+    // A month and half a second at 60 fps wraps to half a second
+    let timeline = Timeline::default().with_period(Some(3600.0));
+    let time = timeline.time_at(60 * 86400 * 30 + 30);
+    assert_eq!(time, 2592000.5);
+    assert_eq!(timeline.wrap_time(time), 0.5);
+    assert_eq!(Timeline::default().wrap_time(2.5), 2.5);
+    let time = 1e6 + 1.0 / 3.0;
+    let [hi, lo] = split_time(time);
+    assert!((hi as f64 + lo as f64 - time).abs() < 1e-9);
+    assert!((hi as f64 - time).abs() > 1e-3)
+}
+
+// ------------------------------------------------------------
+
+// Calendar date in UTC, laid out as [year, month, day, seconds]
+#[derive(Clone, Copy)]
+pub struct Date
+{
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub seconds: f64
+}
+
+impl Date
+{
+    pub fn now() -> Self
+    {
+        let since_epoch = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        Self::from_unix(since_epoch)
+    }
+
+    // Days to civil conversion from
+    // http://howardhinnant.github.io/date_algorithms.html
+    pub fn from_unix(since_epoch: f64) -> Self
+    {
+        let days = (since_epoch / 86400.0).floor();
+        let seconds = since_epoch - days * 86400.0;
+        let z = days as i64 + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + (month <= 2) as i64) as i32;
+        Self{year, month, day, seconds}
+    }
+}
+
+impl From<Date> for [f32; 4]
+{
+    fn from(date: Date) -> Self
+    {
+        [
+            date.year as f32,
+            date.month as f32,
+            date.day as f32,
+            date.seconds as f32
+        ]
+    }
+}
+
+// ------------------------------------------------------------
+
+#[test]
+fn date() -> ()
+{
+    for (since_epoch, [year, month, day], seconds) in
+    [
+        (0.0, [1970, 1, 1], 0.0),
+        (951782400.5, [2000, 2, 29], 0.5),
+        (1700000000.0, [2023, 11, 14], 80000.0)
+    ]
+    {
+        let date = Date::from_unix(since_epoch);
+        assert_eq!([date.year, date.month as _, date.day as _], [year, month, day]);
+        assert_eq!(date.seconds, seconds)
+    }
+}
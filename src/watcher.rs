@@ -125,3 +125,40 @@ impl CodeWatcher
     }
 }
 
+
+// ------------------------------------------------------------
+
+// Editors that save by replacing the file end the watch
+// on the old one, it is then set up again on the new file
+pub struct AssetWatcher(FileWatcher);
+
+impl AssetWatcher
+{
+    const DELAY: Duration = Duration::from_millis(100);
+
+    pub fn new(path: impl AsRef<Path>) -> anyhow::Result<Self>
+    {
+        FileWatcher::watch(path, Self::DELAY).map(Self)
+    }
+
+    pub fn refresh(&mut self) -> anyhow::Result<bool>
+    {
+        match self.0.event()?
+        {
+            Some(FileEvent::Write) => Ok(true),
+            Some(FileEvent::Remove | FileEvent::Rename(_)) =>
+            {
+                match self.0.filepath().is_file()
+                {
+                    true =>
+                    {
+                        self.0 = FileWatcher::watch(self.0.filepath(), Self::DELAY)?;
+                        Ok(true)
+                    }
+                    false => Ok(false)
+                }
+            }
+            None => Ok(false)
+        }
+    }
+}
@@ -1,6 +1,7 @@
 
 use std::ops::*;
 use winit::{window::*, event_loop::*, dpi::*};
+use super::{logger::{self, Level}, shaders::GLSLVersion};
 
 // ------------------------------------------------------------
 
@@ -41,6 +42,26 @@ impl Window
     {
         self.0.drag_window()
     }
+
+    pub fn set_always_on_top(&self, always_on_top: bool) -> ()
+    {
+        self.0.set_always_on_top(always_on_top)
+    }
+
+    // Display and window ids when running on X11
+    #[cfg(any
+    (
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    pub fn xlib(&self) -> Option<(*mut std::ffi::c_void, std::os::raw::c_ulong)>
+    {
+        use winit::platform::unix::WindowExtUnix;
+        Some((self.0.xlib_display()?, self.0.xlib_window()?))
+    }
 }
 
 // ------------------------------------------------------------
@@ -61,13 +82,79 @@ impl std::error::Error for GLWindowCreateError {}
 
 // ------------------------------------------------------------
 
-pub struct GLContextConfiguration
+// OpenGL version and whether the compatibility profile is needed or provided
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct GLVersion
 {
     pub version: [u8; 2],
-    pub profile: GLProfile,
+    pub compatibility: bool
+}
+
+impl std::fmt::Display for GLVersion
+{
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>)
+        -> std::fmt::Result
+    {
+        let [major, minor] = self.version;
+        let profile = match self.compatibility
+        {
+            true => "compatibility",
+            false => "core"
+        };
+        write!(formatter, "{major}.{minor} {profile}")
+    }
+}
+
+impl GLVersion
+{
+    // Oldest context compiling both the given GLSL version and the built-in
+    // 3.30 shaders. GLSL 1.30 and older need the compatibility profile, ES
+    // sources need the ARB_ES*_compatibility version that made them core
+    pub fn for_glsl(glsl: GLSLVersion) -> Self
+    {
+        let version = |version| Self{version, compatibility: false};
+        match (glsl.es, glsl.number)
+        {
+            (true, 100) => version([4, 1]),
+            (true, 300) => version([4, 3]),
+            (true, 310) => version([4, 5]),
+            (true, _) => version([4, 6]),
+            (false, ..=130) => Self{version: [3, 3], compatibility: true},
+            (false, ..=330) => version([3, 3]),
+            (false, number) => version([(number / 100) as u8, (number / 10 % 10) as u8])
+        }
+    }
+
+    // macOS only provides core profiles past OpenGL 2.1
+    pub const COMPATIBILITY_PROFILE: bool = !cfg!(target_os = "macos");
+
+    pub fn satisfies(&self, required: &Self) -> bool
+    {
+        self.version >= required.version
+            && (self.compatibility || !required.compatibility)
+    }
+
+    // Whether a context of this version can be created, given the versions
+    // that could not, as no newer one can be either
+    pub fn creatable(&self, unavailable: &[GLVersion]) -> bool
+    {
+        (Self::COMPATIBILITY_PROFILE || !self.compatibility)
+            && !unavailable.iter().any(|version| self.satisfies(version))
+    }
+}
+
+// ------------------------------------------------------------
+
+#[derive(Clone, Copy)]
+pub struct GLContextConfiguration
+{
+    pub version: GLVersion,
     pub srgb: bool,
     pub double_buffer: bool,
-    pub vsync: bool
+    pub vsync: bool,
+    // Minimum severity of the KHR_debug messages that get reported, when the
+    // context supports them. raw-gl-context cannot request a debug context
+    pub debug: Option<ogl::DebugSeverity>
 }
 
 impl Default for GLContextConfiguration
@@ -76,11 +163,15 @@ impl Default for GLContextConfiguration
     {
         Self
         {
-            version: [3, 2],
-            profile: GLProfile::Core,
+            version: GLVersion{version: [3, 3], compatibility: false},
             srgb: true,
             double_buffer: true,
-            vsync: false
+            vsync: false,
+            debug: match cfg!(debug_assertions)
+            {
+                true => Some(ogl::DebugSeverity::Low),
+                false => None
+            }
         }
     }
 }
@@ -89,11 +180,15 @@ impl From<GLContextConfiguration> for raw_gl_context::GlConfig
 {
     fn from(configuration: GLContextConfiguration) -> Self
     {
-        let [major, minor] = configuration.version;
+        let [major, minor] = configuration.version.version;
         Self
         {
             version: (major, minor),
-            profile: configuration.profile,
+            profile: match configuration.version.compatibility
+            {
+                true => GLProfile::Compatibility,
+                false => GLProfile::Core
+            },
             srgb: configuration.srgb,
             double_buffer: configuration.double_buffer,
             vsync: configuration.vsync,
@@ -108,7 +203,12 @@ pub struct GLWindow
 {
     inner: Window,
     context: raw_gl_context::GlContext,
-    pointers: ogl::FunctionPointers
+    pointers: ogl::FunctionPointers,
+    version: GLVersion,
+    // Requested versions that could not be created
+    unavailable: Vec<GLVersion>,
+    #[allow(unused)]
+    debug: Option<ogl::DebugOutput>
 }
 
 impl Deref for GLWindow
@@ -130,21 +230,73 @@ impl DerefMut for GLWindow
 
 impl GLWindow
 {
+    // Configurations are tried in order, the last error is returned
     pub fn new
     (
         window: Window,
-        configuration: GLContextConfiguration
+        configurations: impl IntoIterator<Item = GLContextConfiguration>
     ) -> Result<Self, GLWindowCreateError>
     {
-        let context = raw_gl_context::GlContext::create
-        (
-            &window.0,
-            configuration.into()
-        ).map_err(GLWindowCreateError)?;
+        let mut result = Err(raw_gl_context::GlError::CreationFailed);
+        let mut debug = None;
+        let mut unavailable = vec!();
+        for configuration in configurations
+        {
+            debug = configuration.debug;
+            result = raw_gl_context::GlContext::create
+            (
+                &window.0,
+                configuration.into()
+            );
+            match &result
+            {
+                Ok(_) => break,
+                Err(error) =>
+                {
+                    unavailable.push(configuration.version);
+                    logger::debug
+                    (
+                        format!("Could not create an OpenGL {} context: {error:?}", configuration.version)
+                    )
+                }
+            }
+        }
+        let context = result.map_err(GLWindowCreateError)?;
         context.make_current();
         let pointers = ogl::FunctionPointers
             ::load(|s| context.get_proc_address(s));
-        Ok(Self{inner: window, context, pointers})
+        // Drivers may give a newer context than requested, and
+        // profiles only exist from OpenGL 3.2 onwards
+        let version = [ogl::MAJOR_VERSION, ogl::MINOR_VERSION]
+            .map(|name| pointers.get_integer(name) as u8);
+        let version = GLVersion
+        {
+            version,
+            compatibility: version < [3, 2]
+                || pointers.get_integer(ogl::CONTEXT_PROFILE_MASK)
+                    & ogl::CONTEXT_COMPATIBILITY_PROFILE_BIT as ogl::GLint != 0
+        };
+        logger::info(format!("OpenGL {version} context"));
+        let debug = debug.and_then
+        (
+            |minimum| ogl::DebugOutput::new
+            (
+                &pointers,
+                minimum,
+                |message|
+                {
+                    let level = match message.severity
+                    {
+                        ogl::DebugSeverity::High => Level::Error,
+                        ogl::DebugSeverity::Medium => Level::Warning,
+                        ogl::DebugSeverity::Low => Level::Info,
+                        ogl::DebugSeverity::Notification => Level::Debug
+                    };
+                    logger::log(level, format!("GL {message}"))
+                }
+            )
+        );
+        Ok(Self{inner: window, context, pointers, version, unavailable, debug})
     }
     
     pub fn context(&self) -> &raw_gl_context::GlContext
@@ -156,5 +308,42 @@ impl GLWindow
     {
         &self.pointers
     }
+
+    pub fn version(&self) -> GLVersion
+    {
+        self.version
+    }
+
+    pub fn unavailable(&self) -> &[GLVersion]
+    {
+        &self.unavailable
+    }
 }
 
+
+// ------------------------------------------------------------
+
+#[test]
+fn requirements() -> ()
+{
+    let required = |number, es| GLVersion::for_glsl(GLSLVersion{number, es}).to_string();
+    assert_eq!(required(120, false), "3.3 compatibility");
+    assert_eq!(required(150, false), "3.3 core");
+    assert_eq!(required(450, false), "4.5 core");
+    assert_eq!(required(100, true), "4.1 core");
+    assert_eq!(required(300, true), "4.3 core");
+    let compatibility = GLVersion{version: [4, 6], compatibility: true};
+    let core = GLVersion{version: [4, 1], compatibility: false};
+    assert!(compatibility.satisfies(&GLVersion::for_glsl(GLSLVersion::default())));
+    assert!(!core.satisfies(&GLVersion::for_glsl(GLSLVersion::default())));
+    assert!(!core.satisfies(&GLVersion::for_glsl(GLSLVersion{number: 300, es: true})));
+    // A 4.3 context being unavailable rules out 4.5 but not 4.1
+    let unavailable = [GLVersion{version: [4, 3], compatibility: false}];
+    assert!(!GLVersion::for_glsl(GLSLVersion{number: 450, es: false}).creatable(&unavailable));
+    assert!(GLVersion::for_glsl(GLSLVersion{number: 100, es: true}).creatable(&unavailable));
+    assert_eq!
+    (
+        GLVersion::for_glsl(GLSLVersion::default()).creatable(&[]),
+        GLVersion::COMPATIBILITY_PROFILE
+    )
+}